@@ -1,55 +1,57 @@
+// The schema and parser are shared with the game, so both agree on what a valid value is.
 #[cfg(not(debug_assertions))]
-use std::{collections::HashMap, fs::File, io::Read};
-
+#[path = "src/env/vars.rs"]
+#[allow(dead_code)]
+mod vars;
 #[cfg(not(debug_assertions))]
-fn build_env_map<'a>(file_content: &'a str) -> HashMap<&'a str, &'a str> {
-    let mut map  = HashMap::new();
-    for line in file_content.lines() {
-        // Ignore full line comments
-        if line.trim_start().starts_with("#") { continue };
-        // Ignore empty lines.
-        if line.trim().is_empty() { continue };
-
-        // Split line on =
-        let (name, value) = match line.split("=").take(2).collect::<Vec<_>>()[..] {
-            [a, b, ..] => (a, b),
-            _ => unreachable!()
-        };
-        
-        // Trim the name "  NAME    = .." => "NAME"
-        let name = name.trim();
-
-        // Trim the value and remove comments
-        let value = value.split("#").next().unwrap().trim();
-
-        if map.contains_key(name) { panic!("Found a duplicate definition for '{}' in '.env'. Values: (1)={}, (2)={}", name, map.get(name).unwrap(), value) };
-        map.insert(name,value);
-    }
-
-    map
-}
+#[path = "src/env/parse.rs"]
+#[allow(dead_code)]
+mod parse;
 
 // Building production
 #[cfg(not(debug_assertions))]
 fn main() {
-    // Only rerun if .env changes.
-    println!("cargo::rerun-if-changed=.env");
-    
-    let Ok(mut file) = File::open(".env") else { panic!("Build failed: Could not open/find .env file."); };
+    use parse::{ConfigError, DEFAULTS_FILE, EnvConfig, apply, parse_table};
+    use vars::EnvVariable;
+
+    // Only rerun if the defaults change.
+    println!("cargo::rerun-if-changed={DEFAULTS_FILE}");
 
-    let mut s = String::new();
-    file.read_to_string(&mut s).expect("Build failed: Could not read .env file.");    
+    let Ok(content) = std::fs::read_to_string(DEFAULTS_FILE) else { panic!("Build failed: Could not open/find {DEFAULTS_FILE} file."); };
 
-    let map = build_env_map(&s);
-    
-    let mut generated = String::new();
-    generated += r#"pub fn acquire_string(var: &EnvVariable) -> &'static str {use EnvVariable::*;match var {"#;
+    let mut errors = Vec::new();
+    let config = match parse_table(&content) {
+        Ok(table) => {
+            // Release builds have nothing to fall back on, every variable must be given.
+            for var in EnvVariable::ALL {
+                if !table.contains_key(var.as_ref()) {
+                    errors.push(ConfigError::new(var.as_ref(), format!("missing from {DEFAULTS_FILE}")));
+                }
+            }
 
-    for (key, value) in map {
-        generated += &format!("\n{key} => \"{value}\",");
-    }
+            // The environment of the build is not the environment of the game.
+            let (config, apply_errors) = apply(EnvConfig::default(), &table, |_| None);
+            errors.extend(apply_errors);
+            config
+        },
+        Err(err) => {
+            errors.push(err);
+            EnvConfig::default()
+        }
+    };
 
-    generated += "}}";
+    // Every problem becomes its own compile error, naming the key.
+    let generated = if errors.is_empty() {
+        let EnvConfig { chunk_width, chunk_height, cell_size, cell_scale, camera_speed } = config;
+        format!(
+            "const EMBEDDED_DEFAULTS: EnvConfig = EnvConfig {{ chunk_width: {chunk_width}, chunk_height: {chunk_height}, cell_size: {cell_size}, cell_scale: {cell_scale:?}, camera_speed: {camera_speed:?} }};\n"
+        )
+    } else {
+        errors.iter().map(|err| {
+            let message = if err.key == DEFAULTS_FILE { err.to_string() } else { format!("{DEFAULTS_FILE}: {err}") };
+            format!("compile_error!({message:?});\n")
+        }).collect()
+    };
 
     let out_dir = std::env::var("OUT_DIR").unwrap();
     std::fs::write(
@@ -59,4 +61,4 @@ fn main() {
 }
 
 #[cfg(debug_assertions)]
-fn main() {}
\ No newline at end of file
+fn main() {}
@@ -1,76 +1,368 @@
-use std::sync::LazyLock;
-use bevy::{input::mouse::MouseWheel, prelude::*};
-use crate::env::{acquire_num, EnvVariable};
+use bevy::{input::mouse::MouseWheel, prelude::*, window::WindowResized};
+use crate::cell::BoardChanged;
+use crate::config::UserConfig;
+use crate::settings::Settings;
+use crate::game::AppState;
 use crate::grid::Grid;
+use crate::hud::HUD_HEIGHT;
+use crate::input::{Action, Actions};
 
 pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app
+        .init_resource::<MouseDrag>()
+        .add_message::<CenterCamera>()
         .add_systems(Startup, spawn_camera)
-        .add_systems(Update, (scroll_events, move_camera));
+        .add_systems(Update, (
+            scroll_events,
+            apply_zoom,
+            move_camera,
+            edge_pan.run_if(|config: Res<UserConfig>| config.edge_pan),
+            drag_camera,
+            coast_camera,
+            center_camera,
+            clamp_camera,
+        ).chain().run_if(in_state(AppState::InGame)))
+        .add_systems(Update, frame_board.run_if(in_state(AppState::InGame)).before(scroll_events));
     }
 }
 
 #[derive(Component)]
 struct Camera;
 
+/// The projection scale the camera is easing towards.
+#[derive(Component)]
+struct Zoom {
+    target: f32
+}
+
+/// Velocity the camera keeps coasting with after a drag is released, in world units per second.
+#[derive(Component, Default)]
+struct Inertia {
+    velocity: Vec2
+}
+
+/// Pixels the cursor must move with a click button held before it counts as a drag instead of a click.
+const DRAG_THRESHOLD: f32 = 6.0;
+/// How quickly a released drag slows to a stop. Higher stops sooner.
+const INERTIA_FRICTION: f32 = 6.0;
+/// Distance from the edge of the window, in pixels, within which edge panning starts.
+const EDGE_MARGIN: f32 = 16.0;
+
+/// Tracks the mouse button currently dragging the camera.
+/// Buttons only bound to PanDrag pan straight away. Buttons which also click cells (flagging, ...) only pan once they have moved past DRAG_THRESHOLD.
+#[derive(Resource, Default)]
+pub struct MouseDrag {
+    button: Option<MouseButton>,
+    start: Vec2,
+    last: Vec2,
+    panned: bool
+}
+impl MouseDrag {
+    /// Whether the current (or most recently released) press panned the camera.
+    /// A press which panned should not also count as a click.
+    pub fn panned(&self) -> bool { self.panned }
+}
+
+/// Sent to move the camera so the given world position is in the centre of the screen.
+#[derive(Message)]
+pub struct CenterCamera {
+    pub position: Vec2
+}
+
 fn spawn_camera(
     mut commands: Commands
 ) {
-    commands.spawn((Camera2d, Camera));
+    commands.spawn((Camera2d, Camera, Zoom { target: 1.0 }, Inertia::default()));
+}
+
+
+/// Fewest cells visible along the shorter side of the window when fully zoomed in.
+const MIN_VISIBLE_CELLS: f32 = 2.0;
+/// How quickly the zoom catches up to its target. Higher is snappier.
+const ZOOM_SHARPNESS: f32 = 12.0;
+/// How fast the zoom keys zoom, as the fraction of the scale changed per second.
+const KEY_ZOOM_SPEED: f32 = 1.5;
+
+/// Empty space left around the board when it is framed, as a fraction of the board size.
+const FRAME_MARGIN: f32 = 0.05;
+
+/// Returns the camera position and projection scale which fit the whole board inside of the window, below the HUD.
+fn board_framing(window: &Window, grid: &Grid) -> (Vec2, f32) {
+    let cell_size = grid.cell_size() as f32;
+    let board_size = Vec2::new(grid.width() as f32, grid.height() as f32) * cell_size;
+    let view_size = Vec2::new(window.width(), (window.height() - HUD_HEIGHT).max(1.0));
+
+    let fit = board_size / view_size;
+    let scale = fit.x.max(fit.y) * (1.0 + 2.0 * FRAME_MARGIN);
+
+    // Cell (x, y) is centred on (x, y) * cell_size, so the board is offset by half a cell.
+    // The view is shifted up by half the HUD, so the board sits in the middle of the space below it.
+    let centre = (board_size - cell_size) / 2.0 + Vec2::new(0.0, HUD_HEIGHT / 2.0 * scale);
+    (centre, scale)
 }
 
+/// Returns the (min, max) projection scale.
+/// Zooming in stops once MIN_VISIBLE_CELLS cells fill the window, zooming out once the board
+/// takes up 1 / max_zoom_out of its framed size.
+fn zoom_limits(window: &Window, grid: &Grid, config: &UserConfig) -> (f32, f32) {
+    let cell_size = grid.cell_size() as f32;
+    let min_scale = MIN_VISIBLE_CELLS * cell_size / window.width().min(window.height());
+
+    let (_, fit_scale) = board_framing(window, grid);
+    let max_scale = fit_scale * config.max_zoom_out;
+
+    (min_scale, max_scale.max(min_scale))
+}
 
 fn scroll_events(
     mut evr_scroll: MessageReader<MouseWheel>,
-    mut projection: Query<&mut Projection, With<Camera>>,
+    actions: Actions,
+    time: Res<Time>,
+    grid: Res<Grid>,
+    config: Res<UserConfig>,
+    mut zoom: Query<&mut Zoom, With<Camera>>,
     windows: Query<&Window>,
 ) {
     use bevy::input::mouse::MouseScrollUnit::*;
     let window = windows.single().unwrap();
-    
-    let min_scale_x = 32.0 / window.width();
-    let min_scale_y = 32.0 / window.height();
-    let min_scale = min_scale_x.max(min_scale_y);
+    let (min_scale, max_scale) = zoom_limits(window, &grid, &config);
 
-    let mut projection = projection.single_mut().unwrap();
-    if let Projection::Orthographic(ref mut ortho) = *projection {
-        for ev in evr_scroll.read() {
-            let unit_scale = match ev.unit {
-                Line => 0.1,
-                Pixel => 0.001
-            };
-
-            ortho.scale *= 1.0 - ev.y * unit_scale;
-            ortho.scale = ortho.scale.max(min_scale);
-        }
+    let mut zoom = zoom.single_mut().unwrap();
+    for ev in evr_scroll.read() {
+        let unit_scale = match ev.unit {
+            Line => 0.1,
+            Pixel => 0.001
+        };
+
+        zoom.target *= 1.0 - ev.y * unit_scale;
+    }
+
+    if actions.pressed(Action::ZoomIn) {
+        zoom.target *= 1.0 - KEY_ZOOM_SPEED * time.delta_secs();
+    }
+    if actions.pressed(Action::ZoomOut) {
+        zoom.target *= 1.0 + KEY_ZOOM_SPEED * time.delta_secs();
+    }
+
+    // Clamped every frame, the limits change along with the window and board size.
+    let target = zoom.target.clamp(min_scale, max_scale);
+    if zoom.target != target {
+        zoom.target = target;
     }
 }
 
-const CAMERA_SPEED: LazyLock<f32> = LazyLock::new(|| acquire_num(EnvVariable::CAMERA_SPEED));
+/// Eases the projection scale towards the zoom target.
+/// The world point under the cursor stays fixed while zooming, or the centre of the screen when the cursor is outside of the window.
+fn apply_zoom(
+    time: Res<Time>,
+    windows: Query<&Window>,
+    mut query: Query<(&Zoom, &mut Transform, &mut Projection), With<Camera>>,
+) {
+    let window = windows.single().unwrap();
+    let (zoom, mut transform, mut projection) = query.single_mut().unwrap();
+    let Projection::Orthographic(ref mut ortho) = *projection else { return };
+
+    if ortho.scale == zoom.target { return };
+
+    let t = 1.0 - (-ZOOM_SHARPNESS * time.delta_secs()).exp();
+    let mut scale = ortho.scale.lerp(zoom.target, t);
+    // Snap once close enough, instead of easing forever.
+    if (scale - zoom.target).abs() < zoom.target * 0.001 {
+        scale = zoom.target;
+    }
+
+    // Offset of the cursor from the centre of the window, in logical pixels with y pointing up.
+    let offset = window.cursor_position()
+        .map(|cursor| Vec2::new(cursor.x - window.width() / 2.0, window.height() / 2.0 - cursor.y))
+        .unwrap_or(Vec2::ZERO);
+
+    // The point under the cursor is translation + offset * scale. Keep it the same before and after the scale change.
+    let shift = offset * (ortho.scale - scale);
+    transform.translation.x += shift.x;
+    transform.translation.y += shift.y;
+    ortho.scale = scale;
+}
 
 fn move_camera(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Actions,
     time: Res<Time>,
     grid: Res<Grid>,
+    settings: Res<Settings>,
     mut query: Query<&mut Transform, With<Camera>>,
 ) {
     let mut transform = query.single_mut().unwrap();
 
     // Speed relative to zoom level
-    let speed = *CAMERA_SPEED * (grid.cell_size() as f32) * time.delta_secs();
+    let speed = settings.camera_speed * (grid.cell_size() as f32) * time.delta_secs();
 
-    if keyboard.pressed(KeyCode::ArrowLeft) || keyboard.pressed(KeyCode::KeyA) {
+    if actions.pressed(Action::PanLeft) {
         transform.translation.x -= speed;
     }
-    if keyboard.pressed(KeyCode::ArrowRight) || keyboard.pressed(KeyCode::KeyD) {
+    if actions.pressed(Action::PanRight) {
         transform.translation.x += speed;
     }
-    if keyboard.pressed(KeyCode::ArrowUp) || keyboard.pressed(KeyCode::KeyW) {
+    if actions.pressed(Action::PanUp) {
         transform.translation.y += speed;
     }
-    if keyboard.pressed(KeyCode::ArrowDown) || keyboard.pressed(KeyCode::KeyS) {
+    if actions.pressed(Action::PanDown) {
         transform.translation.y -= speed;
     }
-}
\ No newline at end of file
+}
+
+/// Pans the camera while the cursor is near the edge of the window.
+fn edge_pan(
+    time: Res<Time>,
+    grid: Res<Grid>,
+    settings: Res<Settings>,
+    windows: Query<&Window>,
+    mut query: Query<&mut Transform, With<Camera>>,
+) {
+    let window = windows.single().unwrap();
+    if !window.focused { return };
+    let Some(cursor) = window.cursor_position() else { return };
+
+    let mut direction = Vec2::ZERO;
+    if cursor.x < EDGE_MARGIN { direction.x -= 1.0 };
+    if cursor.x > window.width() - EDGE_MARGIN { direction.x += 1.0 };
+    if cursor.y < EDGE_MARGIN { direction.y += 1.0 };
+    if cursor.y > window.height() - EDGE_MARGIN { direction.y -= 1.0 };
+    if direction == Vec2::ZERO { return };
+
+    let speed = settings.camera_speed * (grid.cell_size() as f32) * time.delta_secs();
+    let mut transform = query.single_mut().unwrap();
+    transform.translation += (direction.normalize() * speed).extend(0.0);
+}
+
+/// Drags the board along with the cursor while a PanDrag button is held.
+fn drag_camera(
+    time: Res<Time>,
+    actions: Actions,
+    mut drag: ResMut<MouseDrag>,
+    windows: Query<&Window>,
+    mut query: Query<(&mut Transform, &Projection, &mut Inertia), With<Camera>>,
+) {
+    let window = windows.single().unwrap();
+    let cursor = window.cursor_position();
+    let (mut transform, projection, mut inertia) = query.single_mut().unwrap();
+
+    if drag.button.is_none() {
+        // The previous drag ended last frame, its release has been seen by every click.
+        drag.panned = false;
+
+        for button in actions.mouse_buttons(Action::PanDrag) {
+            if !actions.mouse().just_pressed(button) { continue };
+            let Some(cursor) = cursor else { continue };
+
+            let panned = !actions.is_click_button(button);
+            *drag = MouseDrag { button: Some(button), start: cursor, last: cursor, panned };
+            inertia.velocity = Vec2::ZERO;
+            break;
+        }
+    }
+
+    let Some(button) = drag.button else { return };
+    // Released, the camera keeps coasting with its last velocity.
+    if !actions.mouse().pressed(button) {
+        drag.button = None;
+        return;
+    }
+    let Some(cursor) = cursor else { return };
+
+    if !drag.panned && cursor.distance(drag.start) > DRAG_THRESHOLD {
+        drag.panned = true;
+    }
+
+    if drag.panned {
+        let Projection::Orthographic(ref ortho) = *projection else { return };
+
+        // Screen y points down, world y points up.
+        let delta = cursor - drag.last;
+        let world_delta = Vec2::new(-delta.x, delta.y) * ortho.scale;
+        transform.translation += world_delta.extend(0.0);
+
+        // Smoothed, so a single jittery frame doesn't decide the release velocity.
+        if time.delta_secs() > 0.0 {
+            inertia.velocity = inertia.velocity.lerp(world_delta / time.delta_secs(), 0.5);
+        }
+    }
+    drag.last = cursor;
+}
+
+/// Keeps the camera moving after a drag is released, slowing down over time.
+fn coast_camera(
+    time: Res<Time>,
+    drag: Res<MouseDrag>,
+    mut query: Query<(&mut Transform, &mut Inertia), With<Camera>>,
+) {
+    if drag.button.is_some() { return };
+
+    let (mut transform, mut inertia) = query.single_mut().unwrap();
+    if inertia.velocity == Vec2::ZERO { return };
+
+    transform.translation += (inertia.velocity * time.delta_secs()).extend(0.0);
+    inertia.velocity *= (-INERTIA_FRICTION * time.delta_secs()).exp();
+    if inertia.velocity.length() < 1.0 {
+        inertia.velocity = Vec2::ZERO;
+    }
+}
+
+fn center_camera(
+    mut reader: MessageReader<CenterCamera>,
+    mut query: Query<(&mut Transform, &mut Inertia), With<Camera>>,
+) {
+    let Some(CenterCamera { position }) = reader.read().last() else { return };
+
+    let (mut transform, mut inertia) = query.single_mut().unwrap();
+    transform.translation = position.extend(transform.translation.z);
+    inertia.velocity = Vec2::ZERO;
+}
+
+/// Keeps the centre of the screen over the board.
+fn clamp_camera(
+    grid: Res<Grid>,
+    mut query: Query<(&mut Transform, &mut Inertia), With<Camera>>,
+) {
+    let (mut transform, mut inertia) = query.single_mut().unwrap();
+
+    // Cell (x, y) is centred on (x, y) * cell_size.
+    let cell_size = grid.cell_size() as f32;
+    let min = Vec2::splat(-cell_size / 2.0);
+    let max = Vec2::new(grid.width() as f32, grid.height() as f32) * cell_size - cell_size / 2.0;
+
+    let position = transform.translation.truncate();
+    let clamped = position.clamp(min, max);
+    if clamped == position { return };
+
+    transform.translation = clamped.extend(transform.translation.z);
+    // Stop coasting along the axes which hit the edge.
+    if clamped.x != position.x { inertia.velocity.x = 0.0 };
+    if clamped.y != position.y { inertia.velocity.y = 0.0 };
+}
+
+/// Centres the camera on the board and zooms to fit all of it.
+/// Happens whenever the board changes (a new one, or a new cell size), the window is resized, or FitBoard is pressed.
+fn frame_board(
+    actions: Actions,
+    grid: Res<Grid>,
+    mut board_changed: MessageReader<BoardChanged>,
+    mut resized: MessageReader<WindowResized>,
+    windows: Query<&Window>,
+    mut query: Query<(&mut Transform, &mut Projection, &mut Zoom, &mut Inertia), With<Camera>>,
+) {
+    let new_board = board_changed.read().count() > 0;
+    let resized = resized.read().count() > 0;
+    if !new_board && !resized && !actions.just_pressed(Action::FitBoard) { return };
+
+    let window = windows.single().unwrap();
+    let (centre, scale) = board_framing(window, &grid);
+
+    let (mut transform, mut projection, mut zoom, mut inertia) = query.single_mut().unwrap();
+    transform.translation = centre.extend(transform.translation.z);
+    zoom.target = scale;
+    inertia.velocity = Vec2::ZERO;
+    if let Projection::Orthographic(ref mut ortho) = *projection {
+        ortho.scale = scale;
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{layout::CellType, theme::CellTile};
+
+/// Bits 0-3: number of neighboring mines of an air cell, or whether a mine exploded.
+const LOW_MASK: u8 = 0b0000_1111;
+/// Bits 4-5: the kind of cell.
+const KIND_SHIFT: u8 = 4;
+const KIND_MASK: u8 = 0b0011_0000;
+/// Bits 6-7: what covers the cell.
+const COVER_SHIFT: u8 = 6;
+const COVER_MASK: u8 = 0b1100_0000;
+
+const AIR: u8 = 0;
+const MINE: u8 = 1;
+const WALL: u8 = 2;
+
+/// What the player sees of a cell, before it is drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Cover {
+    #[default]
+    Hidden,
+    Revealed,
+    Flagged,
+    /// Marked with a question mark. Unlike a flag, it does not stop the cell from being revealed.
+    Questioned,
+}
+impl Cover {
+    fn from_bits(bits: u8) -> Self {
+        match bits {
+            0 => Cover::Hidden,
+            1 => Cover::Revealed,
+            2 => Cover::Flagged,
+            3 => Cover::Questioned,
+            _ => panic!("Invalid cell cover bits {bits}."),
+        }
+    }
+}
+
+/// Everything there is to know about a cell, packed into a single byte.
+/// Chunks of the grid store these directly, so flood fills and whole board scans never touch the ECS.
+///
+/// Air cells are those which show information about surrounding mines, think the 1, 2, 3, ... in typical minesweeper.
+/// Mines explode when revealed. Both are flaggable, walls are neither revealed nor flagged.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct CellState(u8);
+impl CellState {
+    /// A hidden cell of the given type.
+    pub fn new(cell_type: CellType) -> Self {
+        match cell_type {
+            CellType::Air(n @ 0..=8) => Self((AIR << KIND_SHIFT) | n),
+            CellType::Air(n) => panic!("An air cell can't have {n} neighboring mines."),
+            CellType::Mine => Self(MINE << KIND_SHIFT),
+            CellType::Wall => Self(WALL << KIND_SHIFT),
+        }
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        match (self.0 & KIND_MASK) >> KIND_SHIFT {
+            AIR => CellType::Air(self.0 & LOW_MASK),
+            MINE => CellType::Mine,
+            WALL => CellType::Wall,
+            kind => panic!("Invalid cell kind bits {kind}."),
+        }
+    }
+
+    pub fn is_air(&self) -> bool { matches!(self.cell_type(), CellType::Air(_)) }
+    pub fn is_mine(&self) -> bool { self.cell_type() == CellType::Mine }
+    pub fn is_wall(&self) -> bool { self.cell_type() == CellType::Wall }
+
+    /// Number of mines around an air cell. Always 0 for mines and walls.
+    pub fn neighbor_mines(&self) -> u8 {
+        match self.cell_type() {
+            CellType::Air(n) => n,
+            _ => 0,
+        }
+    }
+
+    pub fn cover(&self) -> Cover {
+        Cover::from_bits((self.0 & COVER_MASK) >> COVER_SHIFT)
+    }
+
+    pub fn set_cover(&mut self, cover: Cover) {
+        self.0 = (self.0 & !COVER_MASK) | ((cover as u8) << COVER_SHIFT);
+    }
+
+    pub fn is_revealed(&self) -> bool { self.cover() == Cover::Revealed }
+    pub fn is_flagged(&self) -> bool { self.cover() == Cover::Flagged }
+
+    /// Whether revealing the cell would uncover it: it is still hidden (or only question-marked), and not a wall.
+    pub fn can_reveal(&self) -> bool {
+        !self.is_wall() && matches!(self.cover(), Cover::Hidden | Cover::Questioned)
+    }
+
+    /// Whether this mine was hit, losing the game or a life.
+    pub fn exploded(&self) -> bool {
+        self.is_mine() && self.0 & LOW_MASK != 0
+    }
+
+    /// Marks the mine as hit.
+    pub fn explode(&mut self) {
+        assert!(self.is_mine(), "Only mines can explode.");
+        self.0 |= 1;
+    }
+
+    /// Tile displaying the flag, mine, or number of the cell. None when there is nothing to show.
+    pub fn content(&self) -> Option<CellTile> {
+        match (self.cell_type(), self.cover()) {
+            (CellType::Wall, _) => Some(CellTile::Wall),
+            // Mines hit with lives to spare stay flagged, but show what they are.
+            (CellType::Mine, _) if self.exploded() => Some(CellTile::Exploded),
+            (_, Cover::Flagged) => Some(CellTile::Flag),
+            (_, Cover::Questioned) => Some(CellTile::Question),
+            (_, Cover::Hidden) | (CellType::Air(0), Cover::Revealed) => None,
+            (CellType::Air(n), Cover::Revealed) => Some(CellTile::Number(n)),
+            (CellType::Mine, Cover::Revealed) => Some(CellTile::Mine),
+        }
+    }
+
+    /// Tile drawn behind the content. None when the cell has no border.
+    pub fn border(&self) -> Option<CellTile> {
+        match (self.cell_type(), self.cover()) {
+            (CellType::Wall, _) | (CellType::Air(0), Cover::Revealed) => None,
+            (_, Cover::Revealed) => Some(CellTile::Revealed),
+            (CellType::Mine, _) if self.exploded() => Some(CellTile::Revealed),
+            _ => Some(CellTile::Hidden),
+        }
+    }
+}
@@ -1,141 +1,215 @@
 use bevy::prelude::*;
-use crate::{cell::{Air, CellBehavior, CellBorder, CellContent, Flagged, Mine, Wall, systems::get_cursor_position}, grid::Grid};
+use crate::{cell::{CellState, Cover}, chunk_mesh::{CellLooks, Layer}, cursor::CellTarget, game::{GameState, MineHit, game_active}, grid::Grid, history::{CellChange, History}, input::Action, layout::CellType, theme::CellTile};
 
 pub struct RevealCellPlugin;
 impl Plugin for RevealCellPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_message::<RevealCell>()
+            .add_message::<ChordCell>()
             .add_message::<UpdateSprite>()
-            .add_systems(Update, (update_sprite, reveal_cell, handle_reveal_click))
+            .add_systems(OnEnter(GameState::Lost), reveal_mines)
+            .add_systems(Update, (
+                update_sprite,
+                (chord_cell, reveal_cell).chain().in_set(RevealCells),
+                (handle_reveal_click, handle_chord_click).run_if(game_active)
+            ))
         ;
     }
 }
 
+/// Reveals the cells the player asked for, sending MineHit for every mine among them.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RevealCells;
+
 #[derive(Message)]
 struct RevealCell {
     pub x: i32,
     pub y: i32
 }
 
+/// Reveals every unflagged neighbor of a revealed number, once as many neighbors are flagged as it has mines.
+#[derive(Message)]
+struct ChordCell {
+    pub x: i32,
+    pub y: i32
+}
+
 #[derive(Message)]
 struct UpdateSprite {
-    pub entity: Entity
+    pub x: i32,
+    pub y: i32,
+    /// Seconds to wait before animating the change, so groups of cells can ripple.
+    pub delay: f32
 }
 
+/// Seconds between a revealed cell and the cells it reveals in turn, making flood fills ripple outwards.
+const RIPPLE_STEP: f32 = 0.03;
+/// Seconds between mines exploding, by their distance to the mine which lost the game.
+const CASCADE_STEP: f32 = 0.06;
+/// Longest an animation waits, so large boards do not take ages to settle.
+const MAX_DELAY: f32 = 1.5;
+
 
 /// Reads messages from UpdateSprite.
-/// Updates the visual of that cell to match the revealed version.
-/// Ie: will display the correct number for neighboring mines.
-/// If there are 0 neighboring mines, the border and content sprites are despawned.
+/// Updates the look of that cell to match its current state.
+/// Ie: will display the correct number for neighboring mines, or the mine once the game is lost.
+/// If there are 0 neighboring mines, the border and content are hidden.
+/// Tiles which appear pop in, and tiles which disappear shrink away, after the delay of the message.
 fn update_sprite(
+    grid: Res<Grid>,
     mut reader: MessageReader<UpdateSprite>,
-    asset_server: Res<AssetServer>,
-    
-    cells: Query<&Air>,
-    children: Query<&Children>,
-    mut content_sprites: Query<(&mut Visibility, &mut Sprite), (With<CellContent>, Without<CellBorder>)>,
-    mut border_sprites: Query<(&mut Visibility, &mut Sprite), (With<CellBorder>, Without<CellContent>)>
+    mut looks: ResMut<CellLooks>,
 ) { 
-    for UpdateSprite { entity} in reader.read() {
-        // Make sure the entity has the air component (only air cells can be revealed)  
-        let Ok(air) = cells.get(*entity) else { panic!("UpdateSprite message sent with invalid entity (Did not have the air componnet).") };
-
-        // Get children of the entity
-        let Ok(children) = children.get(*entity) else { panic!("UpdateSprite message sent with an invalid cell entity (Had no children).") };
-        for &child in children {
-            // Is content sprite
-            if let Ok((mut visibility, mut sprite)) = content_sprites.get_mut(child) {
-                air.update_content(&mut sprite, &mut visibility, &asset_server);
-            }
+    for UpdateSprite { x, y, delay } in reader.read() {
+        let Some(cell) = grid.get(*x, *y) else { panic!("UpdateSprite message sent for a position outside of the grid.") };
+        looks.animate_cell(*x, *y, cell, *delay);
+    }
+}
 
-            // Is border sprite
-            if let Ok((mut visibility, mut sprite)) = border_sprites.get_mut(child) {
-                air.update_border(&mut sprite, &mut visibility, &asset_server);
-            }
+/// Shows the board as it was once the game is lost: every mine which was not flagged is revealed,
+/// and flags placed on cells which were not mines are crossed out. Correct flags are kept.
+/// Mines explode one after the other, starting from the mine which lost the game.
+fn reveal_mines(
+    mut grid: ResMut<Grid>,
+    mut history: ResMut<History>,
+    mut looks: ResMut<CellLooks>,
+    mut writer: MessageWriter<UpdateSprite>,
+) {
+    let exploded: Vec<IVec2> = grid.iter()
+        .filter(|(_, _, cell)| cell.exploded())
+        .map(|(x, y, _)| IVec2::new(x, y))
+        .collect();
+    let hidden_mines: Vec<(i32, i32, CellState)> = grid.iter()
+        .filter(|(_, _, cell)| cell.is_mine() && cell.can_reveal())
+        .collect();
+
+    // Shown along with the mine which lost the game, so undoing it hides them again.
+    let mut changes = Vec::new();
+    for (x, y, before) in hidden_mines {
+        let mut cell = before;
+        cell.set_cover(Cover::Revealed);
+        grid.set(x, y, cell);
+        changes.push(CellChange { x, y, before, after: cell });
+
+        // Chebyshev distance, the same as a flood fill would take.
+        let position = IVec2::new(x, y);
+        let distance = exploded.iter().map(|origin| (position - origin).abs().max_element()).min().unwrap_or(0);
+        let delay = (distance as f32 * CASCADE_STEP).min(MAX_DELAY);
+        writer.write(UpdateSprite { x, y, delay });
+    }
+    history.amend(changes);
+
+    for (x, y, cell) in grid.iter() {
+        if cell.is_air() && cell.is_flagged() {
+            looks.set(x, y, Layer::Content, Some(CellTile::WrongFlag));
         }
     }
 }
 
 /// Reads messages from RevealCell. 
 /// If the cell is an air cell, reveals that cell. If the cell has 0 neighbors, reveals all neighboring cells.
+/// Revealing a mine sends MineHit instead.
 /// Sends the UpdateSprite message, which will update the visual look of every revealed cell.
+/// Everything revealed in one go is recorded as a single move.
 fn reveal_cell(
-    grid: Res<Grid>,
+    mut grid: ResMut<Grid>,
+    mut history: ResMut<History>,
     mut reader: MessageReader<RevealCell>,
     mut writer: MessageWriter<UpdateSprite>,
-
-    mut cells: Query<(Option<&mut Air>, Option<&Mine>, Option<&Wall>, Option<&Flagged>)>,
+    mut hits: MessageWriter<MineHit>,
 ) {
 
     let mut queue = Vec::new();
 
+    // Cells are queued with their distance to the cell the player revealed.
     for RevealCell { x, y } in reader.read() {
-        queue.push((*x, *y));
+        queue.push((*x, *y, 0));
     }  
 
+    let mut changes = Vec::new();
+    let mut hit = false;
     let mut i = 0;
     while i < queue.len() {
-        let (x, y) = queue[i];
+        let (x, y, depth) = queue[i];
         i += 1;
 
-        let Some(entity) = grid.get(x, y) else { continue };
-        let (air, mine, wall, flagged) = match cells.get_mut(entity) {
-            Ok(cell) => cell,
-            Err(_) => continue
-        };
+        let Some(before) = grid.get(x, y) else { continue };
+        let mut cell = before;
 
-        // A flagged cell can't be revealed || A wall cell can't be revealed
-        if flagged.is_some() || wall.is_some() {
-            continue;
-        }
-
-        // Handle air cell
-        if let Some(mut air) = air {
-            if air.revealed { continue }; // Cannot reveal a cell twice.
+        // Flagged cells, walls, and cells revealed already can't be revealed.
+        if !cell.can_reveal() { continue };
 
-            air.revealed = true;
-            writer.write(UpdateSprite { entity });
+        match cell.cell_type() {
+            CellType::Wall => unreachable!(),
+            CellType::Air(neighbor_mines) => {
+                cell.set_cover(Cover::Revealed);
+                grid.set(x, y, cell);
+                changes.push(CellChange { x, y, before, after: cell });
+                writer.write(UpdateSprite { x, y, delay: (depth as f32 * RIPPLE_STEP).min(MAX_DELAY) });
 
-            if air.neighbor_mines == 0 {
-                // Reveal neighhbors
-                for dx in -1..=1 {
-                    for dy in -1..=1 {
-                        if dx == 0 && dy == 0 { continue };
-                        let rx = x as i32 + dx;
-                        let ry = y as i32 + dy;
+                if neighbor_mines == 0 {
+                    // Queue the neighbors
+                    queue.extend(grid.neighbors(x, y).map(|(nx, ny)| (nx, ny, depth + 1)));
+                }
+            },
+            // What happens next is up to the rules of the game.
+            CellType::Mine => {
+                hits.write(MineHit { x, y });
+                hit = true;
+            },
+        }
+    }
 
-                        // Out of bounds
-                        if rx < 0 || ry < 0 || rx >= grid.width() as i32 || ry >= grid.height() as i32 { continue; }
+    // The hit is amended to this move, even when it is all the move did.
+    if hit {
+        history.begin(changes);
+    } else {
+        history.record(changes);
+    }
+}
 
-                        // Queue the neighbors
-                        queue.push((rx, ry));
-                    }
-                }
+/// Reads messages from ChordCell.
+/// Sends RevealCell for every unflagged neighbor of the cell, if it is a revealed number with all of its mines flagged.
+fn chord_cell(
+    grid: Res<Grid>,
+    mut reader: MessageReader<ChordCell>,
+    mut writer: MessageWriter<RevealCell>,
+) {
+    for ChordCell { x, y } in reader.read() {
+        let (x, y) = (*x, *y);
+        let Some(cell) = grid.get(x, y) else { continue };
+        if !cell.is_air() || !cell.is_revealed() || cell.neighbor_mines() == 0 { continue };
+
+        let mut flags = 0;
+        let mut hidden = Vec::new();
+        for (nx, ny) in grid.neighbors(x, y) {
+            match grid.get(nx, ny) {
+                Some(neighbor) if neighbor.is_flagged() => flags += 1,
+                Some(neighbor) if !neighbor.is_wall() => hidden.push((nx, ny)),
+                _ => {}
             }
-
-            continue;
         }
 
-        // Handle mine cell
-        if mine.is_some() {
-            println!("You revealed a mine - game over.");
-        }        
+        if flags != cell.neighbor_mines() { continue };
+        for (nx, ny) in hidden {
+            writer.write(RevealCell { x: nx, y: ny });
+        }
     }
 }
 
+fn handle_chord_click(
+    mut events: MessageWriter<ChordCell>,
+    target: CellTarget,
+) {
+    let Some(block_pos) = target.triggered(Action::Chord) else { return };
+    events.write(ChordCell { x: block_pos.x, y: block_pos.y });
+}
+
 fn handle_reveal_click(
     mut events: MessageWriter<RevealCell>,
-    grid: Res<Grid>,
-    input: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    target: CellTarget,
 ) {
-    if !input.just_pressed(MouseButton::Left) {
-        return;
-    }
-
-    let Some(world_pos) = get_cursor_position(windows, camera_q) else { return; };
-    let block_pos = grid.cell_from_world(world_pos);
+    let Some(block_pos) = target.triggered(Action::Reveal) else { return };
     events.write(RevealCell { x: block_pos.x, y: block_pos.y });
 }
\ No newline at end of file
@@ -1,24 +1,24 @@
-    use rand::Rng;
+    use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
 
+    use crate::cli::CurrentBoard;
+    use crate::game::NewGame;
     use crate::grid::Grid;
-    use crate::cell::{Air, Wall, Mine, CellBehavior};
+    use crate::layout::{BoardLayout, CellType};
+    use crate::settings::{Settings, SettingsChanged};
+    use crate::chunk_mesh::CellLooks;
+    use crate::cell::CellState;
     use bevy::prelude::*;
 
-    #[derive(Debug, Clone)]
-    enum CellType {
-        Air(u8),
-        Mine,
-        Wall
-    }
-
-
-    const MINE_CHANCE: f32 = 12.5;// Percentage
-    const WALL_CHANCE: f32 = 12.5;
+    /// Sent once a new board has replaced the grid, or its cells changed size.
+    /// Anything framing or sized to the board follows this, rather than every change to the cells.
+    #[derive(Message)]
+    pub struct BoardChanged;
 
     /**
      * Returns a flattened X by Y 2d-vector.
+     * Mines and walls are placed at random from the grid's seed, unless a layout is given to copy them from.
      */
-    fn generate_grid(grid_settings: &Grid) -> Vec<CellType> {
+    pub fn generate_grid(grid_settings: &Grid, layout: Option<&BoardLayout>) -> Vec<CellType> {
         use CellType::*;
 
         // Helper fx
@@ -28,21 +28,39 @@
 
         // Flattened width by height 2d array
         let mut grid: Vec<CellType> = vec![Air(0); (grid_settings.width() * grid_settings.height()) as usize];
-        let mut r = rand::rng();
+        let mut r = StdRng::seed_from_u64(grid_settings.seed());
 
-        // Insert bombs
+        // Wall off the border, and collect every cell inside of it.
+        let mut inner = Vec::new();
         for x in 0..grid_settings.width() {
             for y in 0..grid_settings.height() {
                 if x == 0 || y == 0 || x == grid_settings.width() - 1 || y == grid_settings.height() - 1 {
                     grid[idx(x, y)] = Wall;
-                } else if r.random::<f32>() < (MINE_CHANCE / 100.0) {
-                    grid[idx(x, y)] = Mine;
-                } else if r.random::<f32>() < (WALL_CHANCE / 100.0) {
-                    grid[idx(x, y)] = Wall;
+                } else {
+                    inner.push(idx(x, y));
                 }
             }
         }
 
+        if let Some(layout) = layout {
+            // The layout has no border, it starts one cell in.
+            for &i in &inner {
+                let (x, y) = (i as u32 % grid_settings.width(), i as u32 / grid_settings.width());
+                grid[i] = layout.get(x - 1, y - 1);
+            }
+        } else {
+            // Insert bombs, then walls, at random inner cells.
+            inner.shuffle(&mut r);
+            let mines = (grid_settings.mines() as usize).min(inner.len());
+            let walls = (grid_settings.walls() as usize).min(inner.len() - mines);
+            for &i in &inner[..mines] {
+                grid[i] = Mine;
+            }
+            for &i in &inner[mines..mines + walls] {
+                grid[i] = Wall;
+            }
+        }
+
         // Calculate number of surrounding bombs.
         for x in 0..grid_settings.width() {
             for y in 0..grid_settings.height() {
@@ -76,20 +94,68 @@
         return grid;
     }
 
-    pub fn spawn_grid(
-        asset_server: Res<AssetServer>,
+    /// Reads messages from NewGame.
+    /// Throws away the current board and generates a new one in its place, using the current board settings.
+    /// The command line and a loaded layout take the place of the board settings. Sends BoardChanged once done.
+    pub fn respawn_grid(
+        mut reader: MessageReader<NewGame>,
+        board: CurrentBoard,
+        settings: Res<Settings>,
         mut grid: ResMut<Grid>,
+        mut looks: ResMut<CellLooks>,
         mut commands: Commands,
     ) {
-        let grid_cells = generate_grid(&grid);
+        // Only the most recent request matters.
+        let Some(new_game) = reader.read().last() else { return };
+        let seed = new_game.seed.unwrap_or_else(rand::random);
+
+        *grid = Grid::new(&board.settings(), &settings);
+        looks.reset(&grid, &mut commands);
+
+        populate_grid(&mut grid, &mut looks, seed, board.layout());
+        commands.write_message(BoardChanged);
+    }
+
+    /// Reads messages from SettingsChanged.
+    /// Redraws every cell of the current board at the new cell size. Sends BoardChanged when it did.
+    pub fn rescale_grid(
+        mut reader: MessageReader<SettingsChanged>,
+        mut writer: MessageWriter<BoardChanged>,
+        settings: Res<Settings>,
+        mut grid: ResMut<Grid>,
+        mut looks: ResMut<CellLooks>,
+    ) {
+        if reader.read().count() == 0 { return };
+        if grid.scale() == settings.cell_scale && grid.cell_size() == settings.scaled_cell_size() { return };
+
+        grid.set_scale(&settings);
+        looks.redraw();
+        writer.write(BoardChanged);
+    }
+
+    /// Despawns the meshes of the current board.
+    pub fn despawn_grid(
+        grid: Res<Grid>,
+        mut looks: ResMut<CellLooks>,
+        mut commands: Commands,
+    ) {
+        looks.reset(&grid, &mut commands);
+    }
+
+    fn populate_grid(
+        grid: &mut Grid,
+        looks: &mut CellLooks,
+        seed: u64,
+        layout: Option<&BoardLayout>,
+    ) {
+        grid.set_seed(seed);
+        let grid_cells = generate_grid(grid, layout);
         for (index, cell) in grid_cells.iter().enumerate() {
             let x = index as i32 % grid.width() as i32;
             let y = index as i32 / grid.width() as i32;
 
-            match cell {
-                CellType::Air(n) => (Air { neighbor_mines: *n, revealed: false }).spawn(&mut commands, &mut grid, &asset_server, x, y),
-                CellType::Mine => Mine.spawn(&mut commands, &mut grid, &asset_server, x, y),
-                CellType::Wall => Wall.spawn(&mut commands, &mut grid, &asset_server, x, y)
-            };
+            let state = CellState::new(*cell);
+            grid.set(x, y, state);
+            looks.set_cell(x, y, state);
         }
     }
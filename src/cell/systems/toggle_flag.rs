@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use crate::cell::*;
+use crate::{cell::Cover, chunk_mesh::CellLooks, config::UserConfig, cursor::CellTarget, grid::Grid, history::{CellChange, History}, input::Action};
 
 pub fn get_cursor_position(
     windows: Query<&Window>,
@@ -16,62 +16,29 @@ pub fn get_cursor_position(
     })
 }
 
+/// Flags the targeted cell, or takes its flag away. The flag pops in, and shrinks away when removed.
+/// With question marks turned on, a flag turns into a question mark first.
 pub fn toggle_flag(
-    grid: Res<Grid>,
-    input: Res<ButtonInput<MouseButton>>,
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-
-    windows: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    children_q: Query<&Children>,
-    mut cells: Query<(Entity, Option<&Air>, Option<&Wall>, Option<&Flagged>)>,
-    mut content_sprites: Query<(&mut Sprite, &mut Visibility), With<CellContent>>,
+    config: Res<UserConfig>,
+    mut grid: ResMut<Grid>,
+    mut history: ResMut<History>,
+    target: CellTarget,
+    mut looks: ResMut<CellLooks>,
 ) {
-    if !input.just_pressed(MouseButton::Right) {
-        return;
-    }
-
-    let Some(world_pos) = get_cursor_position(windows, camera_q) else { return };
-    let cell_pos = grid.cell_from_world(world_pos);
-    let Some(cell_entity) = grid.get(cell_pos.x, cell_pos.y) else { return };
-
-    let (entity, air, wall, flagged) = match cells.get_mut(cell_entity) {
-        Ok(cell) => cell,
-        Err(_) => return, // entity has no Cell component
-    };
-
-    // Wall cannot be flagged.
-    if wall.is_some() { return }
-    // Revealed cells cannot be flagged.
-    if let Some(Air { revealed, .. }) = air && *revealed { return }
-
-    // Add / remove the flagged component
-    if flagged.is_some() {
-        commands.entity(entity).remove::<Flagged>();
-    } else {
-        commands.entity(entity).insert(Flagged);
+    let Some(cell_pos) = target.triggered(Action::Flag) else { return };
+    let Some(before) = grid.get(cell_pos.x, cell_pos.y) else { return };
+    let mut cell = before;
+
+    // Walls, revealed cells and mines which were hit cannot be flagged.
+    match cell.cover() {
+        _ if cell.is_wall() || cell.exploded() => return,
+        Cover::Revealed => return,
+        Cover::Hidden => cell.set_cover(Cover::Flagged),
+        Cover::Flagged if config.question_marks => cell.set_cover(Cover::Questioned),
+        Cover::Flagged | Cover::Questioned => cell.set_cover(Cover::Hidden),
     }
 
-    // Get the children of the entity
-    let children = match children_q.get(cell_entity) {
-        Ok(children) => children,
-        Err(_) => return, // entity has no children
-    };
-
-    // Toggle visibility of the child sprites
-    for child in children.iter() {
-        if let Ok((mut sprite, mut visibility)) = content_sprites.get_mut(child) {
-            if !flagged.is_some() {
-                sprite.image = asset_server.load("flag.png");
-                sprite.color = Color::linear_rgb(1.0, 0.0, 0.0);
-            };
-            
-            *visibility = if !flagged.is_some() {
-                Visibility::Visible
-            } else {
-                Visibility::Hidden
-            };
-        }
-    }
-}
\ No newline at end of file
+    grid.set(cell_pos.x, cell_pos.y, cell);
+    history.record(vec![CellChange { x: cell_pos.x, y: cell_pos.y, before, after: cell }]);
+    looks.animate_cell(cell_pos.x, cell_pos.y, cell, 0.0);
+}
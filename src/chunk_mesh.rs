@@ -0,0 +1,437 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::cell::CellState;
+use crate::game::AppState;
+use crate::grid::Grid;
+use crate::theme::{CellTile, Theme};
+use crate::tween::{Tween, TweenKind};
+
+pub struct ChunkMeshPlugin;
+impl Plugin for ChunkMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CellLooks>()
+            .add_systems(Update, (
+                advance_tweens,
+                build_chunk_meshes,
+            ).chain().run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Height of the board meshes, below the cursor.
+const CHUNK_Z: f32 = 1.0;
+
+/// The two tiles a cell is drawn with, the content on top of the border.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layer {
+    Border,
+    Content,
+}
+
+/// One tile of a cell, and how it is being animated.
+#[derive(Clone, Copy, Default, Debug)]
+struct TileLook {
+    tile: Option<CellTile>,
+    tween: Option<Tween>,
+}
+impl TileLook {
+    fn scale(&self) -> f32 {
+        self.tween.map_or(1.0, |tween| tween.scale())
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+struct CellLook {
+    border: TileLook,
+    content: TileLook,
+    /// Whether the cell is held down, which shows a hidden border as pressed.
+    pressed: bool,
+}
+impl CellLook {
+    /// The border as it is drawn, taking the cell being held down into account.
+    fn drawn_border(&self) -> TileLook {
+        match self.border.tile {
+            Some(CellTile::Hidden) if self.pressed => TileLook { tile: Some(CellTile::Pressed), ..self.border },
+            _ => self.border,
+        }
+    }
+
+    fn layer(&mut self, layer: Layer) -> &mut TileLook {
+        match layer {
+            Layer::Border => &mut self.border,
+            Layer::Content => &mut self.content,
+        }
+    }
+}
+
+/// The looks of every cell in a chunk, and the mesh they are drawn with.
+struct ChunkLooks {
+    looks: Vec<CellLook>,
+    mesh: Option<(Entity, Handle<Mesh>)>,
+    /// Whether the mesh no longer matches the looks.
+    dirty: bool,
+    /// Whether any of the cells is being animated.
+    tweening: bool,
+}
+
+/// How every cell of the board looks, chunk by chunk. Each chunk is drawn as a single mesh,
+/// which is rebuilt whenever one of its cells changes (or animates).
+/// Holds tiles rather than atlas coordinates, so switching themes only takes rebuilding the meshes.
+#[derive(Resource)]
+pub struct CellLooks {
+    chunk_width: u32,
+    chunk_height: u32,
+    chunks: HashMap<(i32, i32), ChunkLooks>,
+    material: Option<Handle<ColorMaterial>>,
+}
+impl FromWorld for CellLooks {
+    fn from_world(world: &mut World) -> Self {
+        // Cells can be pressed before the first board is built, chunk them like the grid they will be drawn over.
+        world.get_resource::<Grid>().map_or_else(|| Self::new(&Grid::default()), Self::new)
+    }
+}
+impl CellLooks {
+    /// No cells yet, chunked the same as the grid.
+    pub fn new(grid: &Grid) -> Self {
+        Self {
+            chunk_width: grid.chunk_width(),
+            chunk_height: grid.chunk_height(),
+            chunks: HashMap::default(),
+            material: None,
+        }
+    }
+
+    /// Forgets every cell and despawns the meshes, making room for the given grid.
+    pub fn reset(&mut self, grid: &Grid, commands: &mut Commands) {
+        for (entity, _) in self.chunks.drain().filter_map(|(_, chunk)| chunk.mesh) {
+            commands.entity(entity).despawn();
+        }
+        self.chunk_width = grid.chunk_width();
+        self.chunk_height = grid.chunk_height();
+    }
+
+    fn chunk_mut(&mut self, x: i32, y: i32) -> (&mut ChunkLooks, usize) {
+        let (width, height) = (self.chunk_width as i32, self.chunk_height as i32);
+        let chunk = self.chunks.entry((x.div_euclid(width), y.div_euclid(height))).or_insert_with(|| ChunkLooks {
+            looks: vec![CellLook::default(); (width * height) as usize],
+            mesh: None,
+            dirty: true,
+            tweening: false,
+        });
+        (chunk, (y.rem_euclid(height) * width + x.rem_euclid(width)) as usize)
+    }
+
+    /// Shows the tile right away, stopping any animation of it.
+    pub fn set(&mut self, x: i32, y: i32, layer: Layer, tile: Option<CellTile>) {
+        let (chunk, i) = self.chunk_mut(x, y);
+        *chunk.looks[i].layer(layer) = TileLook { tile, tween: None };
+        chunk.dirty = true;
+    }
+
+    /// Shows the tile, popping it in when it appears and shrinking the old tile away when it disappears.
+    /// The animation starts after the delay, in seconds.
+    pub fn animate(&mut self, x: i32, y: i32, layer: Layer, tile: Option<CellTile>, delay: f32) {
+        let (chunk, i) = self.chunk_mut(x, y);
+        let look = chunk.looks[i].layer(layer);
+
+        *look = match (look.tile, tile) {
+            (None, Some(_)) => TileLook { tile, tween: Some(Tween::new(TweenKind::PopIn, delay)) },
+            // The old tile stays until it has shrunk away.
+            (Some(old), None) => TileLook { tile: Some(old), tween: Some(Tween::new(TweenKind::ShrinkOut, delay)) },
+            _ => TileLook { tile, tween: None },
+        };
+        chunk.tweening |= look.tween.is_some();
+        chunk.dirty = true;
+    }
+
+    /// Shows both tiles of the cell right away.
+    pub fn set_cell(&mut self, x: i32, y: i32, state: CellState) {
+        self.set(x, y, Layer::Border, state.border());
+        self.set(x, y, Layer::Content, state.content());
+    }
+
+    /// Animates both tiles of the cell to match its state, after the delay.
+    pub fn animate_cell(&mut self, x: i32, y: i32, state: CellState, delay: f32) {
+        self.animate(x, y, Layer::Border, state.border(), delay);
+        self.animate(x, y, Layer::Content, state.content(), delay);
+    }
+
+    /// Shows the cell as held down (or lets it go), as long as it is hidden.
+    pub fn press(&mut self, x: i32, y: i32, pressed: bool) {
+        let (chunk, i) = self.chunk_mut(x, y);
+        if chunk.looks[i].pressed == pressed { return };
+
+        chunk.looks[i].pressed = pressed;
+        chunk.dirty = true;
+    }
+
+    /// Rebuilds every mesh, after the size of the cells or the theme changed.
+    pub fn redraw(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.dirty = true;
+        }
+    }
+}
+
+fn advance_tweens(
+    time: Res<Time>,
+    mut looks: ResMut<CellLooks>,
+) {
+    if !looks.chunks.values().any(|chunk| chunk.tweening) { return };
+
+    for chunk in looks.chunks.values_mut().filter(|chunk| chunk.tweening) {
+        chunk.tweening = false;
+        for look in chunk.looks.iter_mut() {
+            for tile in [&mut look.border, &mut look.content] {
+                let Some(tween) = &mut tile.tween else { continue };
+
+                if tween.advance(time.delta_secs()) {
+                    if tween.kind() == TweenKind::ShrinkOut {
+                        tile.tile = None;
+                    }
+                    tile.tween = None;
+                } else {
+                    chunk.tweening = true;
+                }
+            }
+        }
+        chunk.dirty = true;
+    }
+}
+
+/// Rebuilds the mesh of every chunk which changed, spawning it the first time.
+fn build_chunk_meshes(
+    grid: Res<Grid>,
+    theme: Res<Theme>,
+    mut looks: ResMut<CellLooks>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    if theme.is_changed() || looks.material.is_none() {
+        let material = ColorMaterial { texture: Some(theme.image().clone()), ..default() };
+        looks.material = Some(materials.add(material));
+        looks.redraw();
+    }
+
+    if !looks.chunks.values().any(|chunk| chunk.dirty) { return };
+
+    let looks = looks.as_mut();
+    let material = looks.material.clone().unwrap();
+    for (&(cx, cy), chunk) in looks.chunks.iter_mut().filter(|(_, chunk)| chunk.dirty) {
+        chunk.dirty = false;
+
+        let origin = IVec2::new(cx * looks.chunk_width as i32, cy * looks.chunk_height as i32);
+        let mesh = chunk_mesh(&grid, &theme, &chunk.looks, looks.chunk_width, origin);
+
+        match &chunk.mesh {
+            // Empty meshes are hidden rather than uploaded.
+            Some((entity, _)) if mesh.count_vertices() == 0 => {
+                commands.entity(*entity).insert(Visibility::Hidden);
+            },
+            Some((entity, handle)) => {
+                if let Some(old) = meshes.get_mut(handle) {
+                    *old = mesh;
+                }
+                commands.entity(*entity).insert((Visibility::Inherited, MeshMaterial2d(material.clone())));
+            },
+            None if mesh.count_vertices() == 0 => {},
+            None => {
+                let handle = meshes.add(mesh);
+                let entity = commands.spawn((
+                    Mesh2d(handle.clone()),
+                    MeshMaterial2d(material.clone()),
+                    Transform::from_xyz(0.0, 0.0, CHUNK_Z),
+                )).id();
+                chunk.mesh = Some((entity, handle));
+            }
+        }
+    }
+}
+
+/// Builds a mesh with a quad for every tile shown in the chunk, centered on its cell.
+fn chunk_mesh(grid: &Grid, theme: &Theme, looks: &[CellLook], chunk_width: u32, origin: IVec2) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    let cell_size = grid.cell_size() as f32;
+    let half_tile = theme.tile_size() as f32 * grid.scale() * 0.5;
+
+    for (i, look) in looks.iter().enumerate() {
+        let x = origin.x + (i as u32 % chunk_width) as i32;
+        let y = origin.y + (i as u32 / chunk_width) as i32;
+        let center = Vec2::new(x as f32 * cell_size, y as f32 * cell_size);
+
+        for tile_look in [look.drawn_border(), look.content] {
+            let Some(tile) = tile_look.tile else { continue };
+            let (uv, color) = theme.tile(tile);
+            let half = half_tile * tile_look.scale();
+            let color = color.to_linear().to_f32_array();
+
+            let start = positions.len() as u32;
+            positions.extend([
+                [center.x - half, center.y - half, 0.0],
+                [center.x + half, center.y - half, 0.0],
+                [center.x + half, center.y + half, 0.0],
+                [center.x - half, center.y + half, 0.0],
+            ]);
+            // Textures are stored top row first.
+            uvs.extend([
+                [uv.min.x, uv.max.y],
+                [uv.max.x, uv.max.y],
+                [uv.max.x, uv.min.y],
+                [uv.min.x, uv.min.y],
+            ]);
+            colors.extend([color; 4]);
+            indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+        }
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::config::BoardSettings;
+    use crate::settings::Settings;
+
+    use super::*;
+
+    #[test]
+    fn cells_can_be_pressed_before_the_first_board() {
+        let mut world = World::new();
+        let mut looks = CellLooks::from_world(&mut world);
+        looks.press(3, 4, true);
+        assert!(looks.chunks.values().any(|chunk| chunk.looks.iter().any(|look| look.pressed)));
+    }
+
+    /// Cells along each side of the board, and of the square revealed at once when updating.
+    const BOARD_SIZE: u32 = 500;
+    const REVEAL_SIZE: i32 = 100;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let board = BoardSettings { width: BOARD_SIZE, height: BOARD_SIZE, ..default() };
+        world.insert_resource(Grid::new(&board, &Settings::default()));
+        world.insert_resource(Theme::unloaded());
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world
+    }
+
+    fn revealed(grid: &Grid, x: i32, y: i32) -> CellState {
+        let mut state = grid.get(x, y).unwrap();
+        state.set_cover(crate::cell::Cover::Revealed);
+        state
+    }
+
+    fn time(run: impl FnOnce()) -> Duration {
+        let start = Instant::now();
+        run();
+        start.elapsed()
+    }
+
+    /// The board as it was drawn before chunk meshes: a parent per cell, with a border and a content sprite.
+    /// Returns the time to spawn the board, and to reveal a square of it.
+    fn sprite_cells() -> (Duration, Duration, usize) {
+        let mut world = world();
+        let mut cells = Vec::new();
+
+        let spawn = time(|| {
+            let grid = world.remove_resource::<Grid>().unwrap();
+            let theme = Theme::unloaded();
+            for (x, y, state) in grid.iter() {
+                let position = Vec3::new(x as f32, y as f32, 0.0) * grid.cell_size() as f32;
+                let mut cell = world.spawn((Transform::from_translation(position), Visibility::Inherited));
+                cell.with_children(|parent| {
+                    for tile in [state.border(), state.content()] {
+                        let visibility = if tile.is_some() { Visibility::Inherited } else { Visibility::Hidden };
+                        parent.spawn((theme.sprite(tile.unwrap_or(CellTile::Hidden)), visibility));
+                    }
+                });
+                cells.push(cell.id());
+            }
+            world.insert_resource(grid);
+        });
+
+        let update = time(|| {
+            let grid = world.remove_resource::<Grid>().unwrap();
+            let theme = Theme::unloaded();
+            for y in 1..=REVEAL_SIZE {
+                for x in 1..=REVEAL_SIZE {
+                    let state = revealed(&grid, x, y);
+                    let children: Vec<Entity> = world.get::<Children>(cells[(y * grid.width() as i32 + x) as usize]).unwrap().to_vec();
+                    for (child, tile) in children.into_iter().zip([state.border(), state.content()]) {
+                        let mut child = world.entity_mut(child);
+                        *child.get_mut::<Visibility>().unwrap() = if tile.is_some() { Visibility::Inherited } else { Visibility::Hidden };
+                        if let Some(tile) = tile {
+                            *child.get_mut::<Sprite>().unwrap() = theme.sprite(tile);
+                        }
+                    }
+                }
+            }
+            world.insert_resource(grid);
+        });
+
+        (spawn, update, world.query::<()>().iter(&world).count())
+    }
+
+    /// The board as it is drawn now, a mesh per chunk.
+    fn chunk_meshes() -> (Duration, Duration, usize) {
+        let mut world = world();
+
+        let spawn = time(|| {
+            let grid = world.remove_resource::<Grid>().unwrap();
+            let mut looks = CellLooks::new(&grid);
+            for (x, y, state) in grid.iter() {
+                looks.set_cell(x, y, state);
+            }
+            world.insert_resource(looks);
+            world.insert_resource(grid);
+            world.run_system_once(build_chunk_meshes).unwrap();
+        });
+
+        let update = time(|| {
+            world.resource_scope(|world, mut looks: Mut<CellLooks>| {
+                let grid = world.resource::<Grid>();
+                for y in 1..=REVEAL_SIZE {
+                    for x in 1..=REVEAL_SIZE {
+                        looks.set_cell(x, y, revealed(grid, x, y));
+                    }
+                }
+            });
+            world.run_system_once(build_chunk_meshes).unwrap();
+        });
+
+        (spawn, update, world.query::<()>().iter(&world).count())
+    }
+
+    /// Compares the time spent on the CPU, the cost of drawing all those sprites is not included.
+    /// Run with `cargo test --release chunk_meshes_outpace_sprites -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn chunk_meshes_outpace_sprites() {
+        let (sprite_spawn, sprite_update, sprite_entities) = sprite_cells();
+        let (mesh_spawn, mesh_update, mesh_entities) = chunk_meshes();
+
+        println!("{BOARD_SIZE}x{BOARD_SIZE} board, revealing {REVEAL_SIZE}x{REVEAL_SIZE} cells");
+        println!("sprites: {sprite_entities} entities, spawn {sprite_spawn:?}, update {sprite_update:?}");
+        println!("meshes:  {mesh_entities} entities, spawn {mesh_spawn:?}, update {mesh_update:?}");
+        assert!(mesh_spawn < sprite_spawn);
+    }
+}
@@ -0,0 +1,289 @@
+use std::path::PathBuf;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::cell::generate_grid;
+use crate::config::{BoardSettings, Difficulty, UserConfig};
+use crate::env;
+use crate::game::{AppState, NewGame};
+use crate::grid::Grid;
+use crate::layout::{self, BoardLayout};
+use crate::settings::Settings;
+
+const HELP: &str = "\
+Minesweeper
+
+USAGE:
+    minesweeper [OPTIONS]
+
+Board options override the board chosen in the menu, which overrides the embedded defaults.
+Passing any of them skips the main menu and starts a game straight away.
+
+OPTIONS:
+    --width <CELLS>     Width of the playable area
+    --height <CELLS>    Height of the playable area
+    --mines <COUNT>     Number of mines on the board
+    --seed <NUMBER>     Seed the board is generated from, random when left out
+    --board <FILE>      Play a board from a text file instead of a generated one:
+                        one line per row, '*' is a mine, '#' a wall and '.' free space.
+                        Can't be combined with --width, --height or --mines
+    --headless          Print the board to stdout in the --board format and exit, without opening a window
+    -h, --help          Print this help and exit";
+
+/// Options given on the command line. Anything left out keeps the user's config.
+#[derive(Debug, Default, Clone)]
+pub struct Cli {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub mines: Option<u32>,
+    pub seed: Option<u64>,
+    pub board: Option<PathBuf>,
+    pub headless: bool,
+}
+impl Cli {
+    /// Parses the arguments of the process. Prints the help or the problem with the arguments and exits, when needed.
+    pub fn from_env() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(Some(cli)) => cli,
+            Ok(None) => {
+                println!("{HELP}");
+                std::process::exit(0);
+            },
+            Err(err) => {
+                eprintln!("error: {err}\n\nRun with --help to see every option.");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// None when the help was asked for.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut cli = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // Both "--width 30" and "--width=30" are accepted.
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+                _ => (arg, None),
+            };
+            let mut value = || inline.clone().or_else(|| args.next()).ok_or(format!("{name} expects a value"));
+
+            match name.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--width" => cli.width = Some(number(&name, value()?)?),
+                "--height" => cli.height = Some(number(&name, value()?)?),
+                "--mines" => cli.mines = Some(number(&name, value()?)?),
+                "--seed" => cli.seed = Some(number(&name, value()?)?),
+                "--board" => cli.board = Some(PathBuf::from(value()?)),
+                "--headless" => cli.headless = true,
+                _ => return Err(format!("unknown option '{name}'")),
+            }
+        }
+
+        // A layout has a size and mines of its own.
+        if cli.board.is_some() && (cli.width.is_some() || cli.height.is_some() || cli.mines.is_some()) {
+            return Err("--board can't be combined with --width, --height or --mines".to_string());
+        }
+
+        Ok(Some(cli))
+    }
+
+    /// Whether any option picks the board, in which case the game starts straight away.
+    pub fn picks_board(&self) -> bool {
+        self.width.is_some() || self.height.is_some() || self.mines.is_some() || self.seed.is_some() || self.board.is_some()
+    }
+
+    /// The board the options pick, layered over the given one.
+    /// A layout replaces it entirely, and keeps its own size and mines.
+    pub fn board(&self, base: &BoardSettings, layout: Option<&BoardLayout>) -> BoardSettings {
+        let mut board = layout.map_or_else(|| base.clone(), BoardLayout::board_settings);
+
+        if layout.is_none() && (self.width.is_some() || self.height.is_some() || self.mines.is_some()) {
+            board.set_difficulty(Difficulty::Custom);
+            board.width = self.width.unwrap_or(board.width);
+            board.height = self.height.unwrap_or(board.height);
+            board.mines = self.mines.unwrap_or(board.mines);
+            board.clamp();
+        }
+
+        if self.seed.is_some() {
+            board.seed = self.seed;
+        }
+        board
+    }
+
+    /// Loads the board file, if one was given.
+    pub fn layout(&self) -> Result<Option<BoardLayout>, String> {
+        self.board.as_deref().map(BoardLayout::load).transpose()
+    }
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: String) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{name} expects a whole number, found '{value}'"))
+}
+
+/// Board options from the command line. Kept out of the UserConfig, so they are never saved,
+/// and only last until the board is changed from the menu.
+#[derive(Resource, Debug, Clone)]
+pub struct CliOverrides {
+    cli: Cli,
+    /// The user's board the game started with, to tell when the menu changes it.
+    menu_board: BoardSettings,
+}
+
+/// The board new games are played on: the user's board, with the command line layered over it.
+#[derive(SystemParam)]
+pub struct CurrentBoard<'w> {
+    config: Res<'w, UserConfig>,
+    overrides: Option<Res<'w, CliOverrides>>,
+    layout: Option<Res<'w, BoardLayout>>,
+}
+impl CurrentBoard<'_> {
+    pub fn settings(&self) -> BoardSettings {
+        match &self.overrides {
+            Some(overrides) => overrides.cli.board(&self.config.board, self.layout()),
+            None => self.layout().map_or_else(|| self.config.board.clone(), BoardLayout::board_settings),
+        }
+    }
+
+    pub fn layout(&self) -> Option<&BoardLayout> {
+        self.layout.as_deref()
+    }
+}
+
+/// Applies the command line to the app. Has to be added after the ConfigPlugin, so it can tell when the user's board changes.
+pub struct CliPlugin {
+    pub cli: Cli,
+    pub layout: Option<BoardLayout>,
+}
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, forget_overrides.run_if(resource_exists::<CliOverrides>));
+
+        if let Some(layout) = &self.layout {
+            app.insert_resource(layout.clone());
+        }
+
+        if self.cli.picks_board() {
+            let menu_board = app.world().resource::<UserConfig>().board.clone();
+            app
+                .insert_resource(CliOverrides { cli: self.cli.clone(), menu_board })
+                .add_systems(Startup, start_game);
+        }
+    }
+}
+
+fn start_game(
+    board: CurrentBoard,
+    mut new_game: MessageWriter<NewGame>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    new_game.write(NewGame { seed: board.settings().seed });
+    next_state.set(AppState::InGame);
+}
+
+/// Changing the board from the menu goes back to the user's board. Only the seed can change without dropping them.
+fn forget_overrides(
+    config: Res<UserConfig>,
+    overrides: Res<CliOverrides>,
+    mut commands: Commands,
+) {
+    let unseeded = |board: &BoardSettings| BoardSettings { seed: None, ..board.clone() };
+    if unseeded(&config.board) != unseeded(&overrides.menu_board) {
+        commands.remove_resource::<CliOverrides>();
+        commands.remove_resource::<BoardLayout>();
+    }
+}
+
+/// Generates the board the game would start with and prints it, without starting the game.
+pub fn run_headless(cli: &Cli, layout: Option<&BoardLayout>) {
+    for err in env::errors() {
+        eprintln!("warning: {err}");
+    }
+
+    let board = cli.board(&UserConfig::load().board, layout);
+    let mut grid = Grid::new(&board, &Settings::default());
+    let seed = board.seed.unwrap_or_else(rand::random);
+    grid.set_seed(seed);
+
+    // The seed goes to stderr, so stdout can be used as a --board file as is.
+    if layout.is_none() {
+        eprintln!("seed: {seed}");
+    }
+    print!("{}", layout::render(&grid, &generate_grid(&grid, layout)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Cli>, String> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let cli = parse(&["--width", "30", "--height=20", "--mines", "99", "--seed", "42", "--headless"]).unwrap().unwrap();
+        assert_eq!(cli.width, Some(30));
+        assert_eq!(cli.height, Some(20));
+        assert_eq!(cli.mines, Some(99));
+        assert_eq!(cli.seed, Some(42));
+        assert!(cli.headless);
+        assert!(cli.picks_board());
+
+        let cli = parse(&["--board", "board.txt", "--seed", "42"]).unwrap().unwrap();
+        assert_eq!(cli.board, Some(PathBuf::from("board.txt")));
+        assert!(cli.picks_board());
+    }
+
+    #[test]
+    fn boards_keep_their_own_size() {
+        for option in ["--width", "--height", "--mines"] {
+            let err = parse(&["--board", "board.txt", option, "10"]).unwrap_err();
+            assert_eq!(err, "--board can't be combined with --width, --height or --mines");
+        }
+
+        let layout = BoardLayout::parse("*....\n.....\n.....\n.....\n....#").unwrap();
+        let cli = Cli { width: Some(30), mines: Some(10), ..Cli::default() };
+        assert_eq!(cli.board(&BoardSettings::default(), Some(&layout)), layout.board_settings());
+    }
+
+    #[test]
+    fn no_options_keep_the_users_board() {
+        let cli = parse(&[]).unwrap().unwrap();
+        assert!(!cli.picks_board());
+        assert!(!cli.headless);
+
+        let base = BoardSettings { seed: Some(7), ..BoardSettings::default() };
+        assert_eq!(cli.board(&base, None), base);
+    }
+
+    #[test]
+    fn help_stops_parsing() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--width", "30", "-h", "--unknown"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert_eq!(parse(&["--colour"]).unwrap_err(), "unknown option '--colour'");
+        assert_eq!(parse(&["30"]).unwrap_err(), "unknown option '30'");
+    }
+
+    #[test]
+    fn missing_values_are_rejected() {
+        assert_eq!(parse(&["--width"]).unwrap_err(), "--width expects a value");
+        assert_eq!(parse(&["--mines", "many"]).unwrap_err(), "--mines expects a whole number, found 'many'");
+        assert_eq!(parse(&["--seed="]).unwrap_err(), "--seed expects a whole number, found ''");
+    }
+
+    #[test]
+    fn options_are_layered_over_the_board() {
+        let cli = parse(&["--width", "12", "--seed", "3"]).unwrap().unwrap();
+        let base = BoardSettings::default();
+        let board = cli.board(&base, None);
+        assert_eq!(board.difficulty, Difficulty::Custom);
+        assert_eq!((board.width, board.height, board.mines, board.seed), (12, base.height, base.mines, Some(3)));
+    }
+}
@@ -0,0 +1,202 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{env, theme::{self, Palette}};
+
+const CONFIG_FILE: &str = "config.toml";
+
+pub struct ConfigPlugin;
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(UserConfig::load())
+            .add_systems(Update, save_config.run_if(resource_changed::<UserConfig>));
+    }
+}
+
+/// Directory holding every file the game writes for the user (config, statistics, ...).
+/// Follows the platform convention: `%APPDATA%` on Windows, `$XDG_CONFIG_HOME` or `~/.config` elsewhere.
+pub fn user_dir() -> PathBuf {
+    let base = std::env::var_os("APPDATA")
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+
+    base.join("minesweeper")
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+    Custom
+}
+impl Difficulty {
+    /// (width, height, mines) of the playable area. None for custom boards.
+    pub fn preset(&self) -> Option<(u32, u32, u32)> {
+        match self {
+            Difficulty::Beginner => Some((9, 9, 10)),
+            Difficulty::Intermediate => Some((16, 16, 40)),
+            Difficulty::Expert => Some((30, 16, 99)),
+            Difficulty::Custom => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom => "Custom"
+        }
+    }
+}
+
+/// Describes the board generated by the next new game.
+/// Width and height are of the playable area, the surrounding wall border is not included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BoardSettings {
+    pub difficulty: Difficulty,
+    pub width: u32,
+    pub height: u32,
+    pub mines: u32,
+    /// Walls placed randomly inside of the playable area.
+    pub walls: u32,
+    /// Seed used when starting a game from the menu. A random seed is used when None.
+    pub seed: Option<u64>
+}
+impl BoardSettings {
+    pub const MIN_SIZE: u32 = 5;
+    pub const MAX_SIZE: u32 = 200;
+
+    pub fn from_difficulty(difficulty: Difficulty) -> Self {
+        let mut settings = Self { difficulty, width: 0, height: 0, mines: 0, walls: 0, seed: None };
+        settings.set_difficulty(difficulty);
+        settings
+    }
+
+    /// Switches to the given difficulty, loading its preset dimensions.
+    /// Switching to custom keeps the current dimensions.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        if let Some((width, height, mines)) = difficulty.preset() {
+            self.width = width;
+            self.height = height;
+            self.mines = mines;
+            self.walls = 0;
+        }
+    }
+
+    /// Keeps the dimensions within bounds, and leaves at least one free cell on the board.
+    pub fn clamp(&mut self) {
+        self.width = self.width.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        self.height = self.height.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+
+        let cells = self.width * self.height;
+        self.mines = self.mines.clamp(1, cells - 1);
+        self.walls = self.walls.min(cells - 1 - self.mines);
+    }
+}
+impl Default for BoardSettings {
+    fn default() -> Self {
+        Self::from_difficulty(Difficulty::Intermediate)
+    }
+}
+
+/// Everything the player can change from the menus. Persisted to the user's config file whenever it changes.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct UserConfig {
+    pub board: BoardSettings,
+    /// Cells per second.
+    pub camera_speed: f32,
+    /// Scale applied to every cell asset.
+    pub cell_scale: f32,
+    /// How far the camera may zoom out, in multiples of the board size.
+    pub max_zoom_out: f32,
+    /// Whether the camera pans when the cursor is at the edge of the window.
+    pub edge_pan: bool,
+    /// Whether flagging a flagged cell marks it with a question mark, rather than clearing it straight away.
+    pub question_marks: bool,
+    /// Whether hitting a mine can be undone. Practice games are left out of the statistics.
+    pub practice: bool,
+    /// Mines the player can hit before losing the game. 1 is classic minesweeper.
+    pub lives: u32,
+    /// Id of the theme cells are drawn with, the file name of its manifest.
+    pub theme: String,
+    /// Colours the theme is drawn with.
+    pub palette: Palette
+}
+impl UserConfig {
+    pub const CAMERA_SPEED_RANGE: (f32, f32) = (0.5, 20.0);
+    pub const CELL_SCALE_RANGE: (f32, f32) = (1.0, 4.0);
+    pub const MAX_ZOOM_OUT_RANGE: (f32, f32) = (1.0, 10.0);
+    pub const LIVES_RANGE: (u32, u32) = (1, 9);
+
+    fn path() -> PathBuf {
+        user_dir().join(CONFIG_FILE)
+    }
+
+    /// Loads the config from the user's config file, falling back to the defaults when it is missing or unreadable.
+    pub fn load() -> Self {
+        let Ok(content) = fs::read_to_string(Self::path()) else { return Self::default() };
+
+        match toml::from_str::<Self>(&content) {
+            Ok(mut config) => {
+                config.clamp();
+                config
+            },
+            Err(err) => {
+                warn!("Ignoring malformed config file '{}': {}", Self::path().display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let content = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::create_dir_all(user_dir())?;
+        fs::write(Self::path(), content)
+    }
+
+    pub fn clamp(&mut self) {
+        self.board.clamp();
+        self.camera_speed = self.camera_speed.clamp(Self::CAMERA_SPEED_RANGE.0, Self::CAMERA_SPEED_RANGE.1);
+        self.cell_scale = self.cell_scale.clamp(Self::CELL_SCALE_RANGE.0, Self::CELL_SCALE_RANGE.1);
+        self.max_zoom_out = self.max_zoom_out.clamp(Self::MAX_ZOOM_OUT_RANGE.0, Self::MAX_ZOOM_OUT_RANGE.1);
+        self.lives = self.lives.clamp(Self::LIVES_RANGE.0, Self::LIVES_RANGE.1);
+    }
+}
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            board: BoardSettings::default(),
+            camera_speed: env::defaults().camera_speed,
+            cell_scale: env::defaults().cell_scale,
+            max_zoom_out: 2.0,
+            edge_pan: false,
+            question_marks: false,
+            practice: false,
+            lives: 1,
+            theme: theme::DEFAULT_THEME.to_string(),
+            palette: Palette::default(),
+        }
+    }
+}
+
+fn save_config(
+    config: Res<UserConfig>,
+) {
+    // Freshly loaded, nothing to write back.
+    if config.is_added() { return };
+
+    if let Err(err) = config.save() {
+        error!("Failed to save config file '{}': {}", UserConfig::path().display(), err);
+    }
+}
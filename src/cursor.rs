@@ -0,0 +1,243 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::camera::{CenterCamera, MouseDrag};
+use crate::cell::{BoardChanged, get_cursor_position};
+use crate::game::{AppState, game_active};
+use crate::grid::Grid;
+use crate::hud::{HUD_HEIGHT, cursor_over_ui};
+use crate::input::{Action, Actions};
+use crate::theme::{CellTile, Theme};
+
+pub struct CursorPlugin;
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CellCursor>()
+            .add_systems(OnEnter(AppState::InGame), spawn_cursor)
+            .add_systems(Update, (
+                reset_cursor,
+                move_cursor.run_if(game_active),
+                hide_cursor,
+                follow_cursor,
+                draw_cursor,
+            ).chain().run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Seconds a cursor key (or button) has to be held before the cursor starts repeating.
+const REPEAT_DELAY: f32 = 0.3;
+/// Seconds between cursor steps while a cursor key is held.
+const REPEAT_INTERVAL: f32 = 0.06;
+/// Cells kept between the cursor and the edge of the screen, the camera follows when the cursor gets closer.
+const FOLLOW_MARGIN: f32 = 1.5;
+
+const CURSOR_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+
+/// The cell selected with the keyboard or a gamepad. Key bound cell actions (reveal, flag, ...) happen here instead of under the mouse.
+#[derive(Resource, Default)]
+pub struct CellCursor {
+    position: IVec2,
+    visible: bool,
+    /// Seconds the cursor keys have been held, none while they are all released.
+    held: Option<f32>,
+}
+
+/// Marks the sprite highlighting the cursor cell.
+#[derive(Component)]
+struct CursorSprite;
+
+/// Finds the cell a cell action was triggered on this frame, if it was.
+/// Keys act on the keyboard cursor, mouse clicks on the cell under the mouse unless they panned the camera or hit the UI.
+#[derive(SystemParam)]
+pub struct CellTarget<'w, 's> {
+    actions: Actions<'w, 's>,
+    grid: Res<'w, Grid>,
+    cursor: Res<'w, CellCursor>,
+    drag: Res<'w, MouseDrag>,
+    windows: Query<'w, 's, &'static Window>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    interactions: Query<'w, 's, &'static Interaction>,
+}
+impl CellTarget<'_, '_> {
+    pub fn triggered(&self, action: Action) -> Option<IVec2> {
+        if self.actions.cursor_just_pressed(action) {
+            return Some(self.cursor.position);
+        }
+
+        if !self.actions.clicked(action) || self.drag.panned() { return None };
+        if cursor_over_ui(self.interactions.as_readonly()) { return None };
+
+        let world_pos = get_cursor_position(self.windows.as_readonly(), self.camera_q.as_readonly())?;
+        Some(self.grid.cell_from_world(world_pos))
+    }
+}
+
+fn spawn_cursor(
+    mut commands: Commands,
+    theme: Res<Theme>,
+) {
+    commands.spawn((
+        Sprite {
+            color: CURSOR_COLOR,
+            ..theme.sprite(CellTile::Hidden)
+        },
+        Transform::from_xyz(0.0, 0.0, 2.0),
+        Visibility::Hidden,
+        CursorSprite,
+        DespawnOnExit(AppState::InGame),
+    ));
+}
+
+/// Puts the cursor back in the middle of a new board, hidden until it is used.
+fn reset_cursor(
+    grid: Res<Grid>,
+    mut board_changed: MessageReader<BoardChanged>,
+    mut cursor: ResMut<CellCursor>,
+) {
+    if board_changed.read().count() == 0 { return };
+
+    *cursor = CellCursor {
+        position: IVec2::new(grid.width() as i32 / 2, grid.height() as i32 / 2),
+        ..default()
+    };
+}
+
+/// Moves the cursor with the cursor keys (or d-pad), repeating while they are held.
+/// Any key or gamepad bound cell action also shows the cursor, so it is clear where the action happened.
+fn move_cursor(
+    time: Res<Time>,
+    grid: Res<Grid>,
+    actions: Actions,
+    mut cursor: ResMut<CellCursor>,
+) {
+    let directions = [
+        (Action::CursorUp, IVec2::Y),
+        (Action::CursorDown, IVec2::NEG_Y),
+        (Action::CursorLeft, IVec2::NEG_X),
+        (Action::CursorRight, IVec2::X),
+    ];
+
+    if [Action::Reveal, Action::Flag, Action::Chord].into_iter().any(|action| actions.cursor_just_pressed(action)) {
+        cursor.visible = true;
+    }
+
+    let step: IVec2 = directions.into_iter()
+        .filter(|(action, _)| actions.pressed(*action))
+        .map(|(_, direction)| direction)
+        .sum();
+    if step == IVec2::ZERO {
+        if cursor.held.is_some() { cursor.held = None };
+        return;
+    }
+
+    // Step once straight away, then repeat every interval once the delay has passed.
+    let repeat = |held: f32| ((held - REPEAT_DELAY) / REPEAT_INTERVAL).floor().max(-1.0);
+    let moved = match cursor.held {
+        None => true,
+        Some(before) => repeat(before + time.delta_secs()) > repeat(before),
+    };
+    cursor.held = Some(cursor.held.map_or(0.0, |held| held + time.delta_secs()));
+    if !moved { return };
+
+    // The first key press only brings back a hidden cursor.
+    if !cursor.visible {
+        cursor.visible = true;
+        return;
+    }
+
+    // Stay inside of the border walls.
+    let max = IVec2::new(grid.width() as i32 - 2, grid.height() as i32 - 2);
+    cursor.position = (cursor.position + step).clamp(IVec2::ONE, max.max(IVec2::ONE));
+}
+
+/// Moving the mouse hands control back to it, hiding the cursor.
+fn hide_cursor(
+    mut cursor_moved: MessageReader<CursorMoved>,
+    mut cursor: ResMut<CellCursor>,
+) {
+    if cursor_moved.read().count() == 0 || !cursor.visible { return };
+    cursor.visible = false;
+}
+
+/// Moves the camera once the cursor gets close to the edge of the screen, just enough to keep it FOLLOW_MARGIN cells away.
+fn follow_cursor(
+    grid: Res<Grid>,
+    cursor: Res<CellCursor>,
+    camera: Query<(&Transform, &Projection), With<Camera2d>>,
+    mut writer: MessageWriter<CenterCamera>,
+) {
+    if !cursor.is_changed() || !cursor.visible { return };
+    let Ok((transform, projection)) = camera.single() else { return };
+    let Projection::Orthographic(ortho) = projection else { return };
+
+    let cell_size = grid.cell_size() as f32;
+    let margin = Vec2::splat(FOLLOW_MARGIN * cell_size);
+    let centre = transform.translation.truncate();
+
+    // The HUD covers the top of the screen.
+    let mut max = ortho.area.max;
+    max.y -= HUD_HEIGHT * ortho.scale;
+
+    let target = cursor.position.as_vec2() * cell_size;
+    let low = centre + ortho.area.min + margin;
+    let high = centre + max - margin;
+
+    // When the screen is too small to keep the margin, centre on the cursor instead.
+    let shift = Vec2::new(
+        if low.x > high.x { target.x - (low.x + high.x) / 2.0 } else { (target.x - high.x).max(0.0) + (target.x - low.x).min(0.0) },
+        if low.y > high.y { target.y - (low.y + high.y) / 2.0 } else { (target.y - high.y).max(0.0) + (target.y - low.y).min(0.0) },
+    );
+    if shift == Vec2::ZERO { return };
+
+    writer.write(CenterCamera { position: centre + shift });
+}
+
+fn draw_cursor(
+    grid: Res<Grid>,
+    cursor: Res<CellCursor>,
+    mut sprite: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<CursorSprite>>,
+) {
+    if !cursor.is_changed() { return };
+    let Ok((mut sprite, mut transform, mut visibility)) = sprite.single_mut() else { return };
+
+    let cell_size = grid.cell_size() as f32;
+    sprite.custom_size = Some(Vec2::splat(cell_size));
+    transform.translation = (cursor.position.as_vec2() * cell_size).extend(transform.translation.z);
+    *visibility = if cursor.visible { Visibility::Visible } else { Visibility::Hidden };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::tests::{connect_gamepad, set_button, test_app};
+
+    use super::*;
+
+    #[test]
+    fn dpad_moves_the_cursor() {
+        let mut app = test_app();
+        app
+            .init_resource::<Time>()
+            .insert_resource(Grid::default())
+            .insert_resource(CellCursor { position: IVec2::new(5, 5), ..default() })
+            .add_systems(Update, move_cursor);
+        let gamepad = connect_gamepad(&mut app);
+        let cursor = |app: &App| { let cursor = app.world().resource::<CellCursor>(); (cursor.position, cursor.visible) };
+
+        // The first press only shows the hidden cursor.
+        set_button(&mut app, gamepad, GamepadButton::DPadRight, 1.0);
+        app.update();
+        assert_eq!(cursor(&app), (IVec2::new(5, 5), true));
+
+        set_button(&mut app, gamepad, GamepadButton::DPadRight, 0.0);
+        app.update();
+        set_button(&mut app, gamepad, GamepadButton::DPadRight, 1.0);
+        app.update();
+        assert_eq!(cursor(&app), (IVec2::new(6, 5), true));
+
+        set_button(&mut app, gamepad, GamepadButton::DPadRight, 0.0);
+        app.update();
+        set_button(&mut app, gamepad, GamepadButton::DPadUp, 1.0);
+        app.update();
+        assert_eq!(cursor(&app), (IVec2::new(6, 6), true));
+    }
+}
@@ -1,22 +1,69 @@
+mod parse;
 mod vars;
+pub use parse::{ConfigError, EnvConfig};
 pub use vars::EnvVariable;
 
-use std::{env, str::FromStr, fmt::Debug};
+use std::sync::OnceLock;
+
+use crate::cell::LARGEST_SPRITE;
+use parse::apply;
+#[cfg(debug_assertions)]
+use parse::{DEFAULTS_FILE, parse_table};
 
 // Debug
+/// Reads the defaults file at startup, so it can be tweaked without rebuilding.
 #[cfg(debug_assertions)]
-pub fn acquire_string(var: &EnvVariable) -> String {
-    env::var(var.as_ref()).expect(&format!("Expected environment variable '{}' which was not set.", var.as_ref()))
+fn load_defaults() -> (EnvConfig, Vec<ConfigError>) {
+    let table = std::fs::read_to_string(DEFAULTS_FILE)
+        .map_err(|err| ConfigError::new(DEFAULTS_FILE, format!("could not read the file: {err}")))
+        .and_then(|content| parse_table(&content));
+
+    match table {
+        Ok(table) => apply(EnvConfig::default(), &table, |key| std::env::var(key).ok()),
+        Err(err) => {
+            let (config, mut errors) = apply(EnvConfig::default(), &toml::Table::new(), |key| std::env::var(key).ok());
+            errors.insert(0, err);
+            (config, errors)
+        }
+    }
 }
 
 // Release
+// Defines EMBEDDED_DEFAULTS, validated by build.rs.
 #[cfg(not(debug_assertions))]
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
-pub fn acquire_num<T>(var: EnvVariable) -> T
-where
-    T: FromStr,
-    T::Err: Debug
-{
-    acquire_string(&var).parse::<T>().expect(&format!("Expected environment variable '{}' to be a parsable u32", var.as_ref()))
-}
\ No newline at end of file
+/// Starts from the defaults embedded at build time, which are known to be valid.
+#[cfg(not(debug_assertions))]
+fn load_defaults() -> (EnvConfig, Vec<ConfigError>) {
+    apply(EMBEDDED_DEFAULTS, &toml::Table::new(), |key| std::env::var(key).ok())
+}
+
+static DEFAULTS: OnceLock<(EnvConfig, Vec<ConfigError>)> = OnceLock::new();
+
+fn loaded() -> &'static (EnvConfig, Vec<ConfigError>) {
+    DEFAULTS.get_or_init(|| {
+        let (mut config, mut errors) = load_defaults();
+
+        // Every cell asset has to fit inside of a cell.
+        let largest_sprite = LARGEST_SPRITE;
+        if config.cell_size < largest_sprite {
+            errors.push(ConfigError::new(EnvVariable::CELL_SIZE.as_ref(), format!(
+                "{} is smaller than the largest cell sprite ({largest_sprite} pixels), using {largest_sprite}", config.cell_size
+            )));
+            config.cell_size = largest_sprite;
+        }
+
+        (config, errors)
+    })
+}
+
+/// The embedded defaults (with environment overrides), loaded the first time they are needed.
+pub fn defaults() -> &'static EnvConfig {
+    &loaded().0
+}
+
+/// Every problem found while loading the defaults.
+pub fn errors() -> &'static [ConfigError] {
+    &loaded().1
+}
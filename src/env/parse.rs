@@ -0,0 +1,177 @@
+// Shared with build.rs, so it may only depend on the schema in vars.rs and the toml crate.
+use std::fmt;
+
+use super::vars::EnvVariable;
+
+// Release builds only read the file from build.rs.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub const DEFAULTS_FILE: &str = "defaults.toml";
+
+/// The typed values of every EnvVariable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvConfig {
+    pub chunk_width: u32,
+    pub chunk_height: u32,
+    pub cell_size: u32,
+    pub cell_scale: f32,
+    pub camera_speed: f32,
+}
+impl EnvConfig {
+    fn set(&mut self, var: EnvVariable, value: f64) {
+        use EnvVariable::*;
+        match var {
+            CHUNK_WIDTH => self.chunk_width = value as u32,
+            CHUNK_HEIGHT => self.chunk_height = value as u32,
+            CELL_SIZE => self.cell_size = value as u32,
+            CELL_SCALE => self.cell_scale = value as f32,
+            CAMERA_SPEED => self.camera_speed = value as f32,
+        }
+    }
+}
+impl Default for EnvConfig {
+    fn default() -> Self {
+        let mut config = Self { chunk_width: 0, chunk_height: 0, cell_size: 0, cell_scale: 0.0, camera_speed: 0.0 };
+        for var in EnvVariable::ALL {
+            config.set(var, var.schema().default);
+        }
+        config
+    }
+}
+
+/// A single problem found while loading the defaults. The affected value keeps what it was before.
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    /// The variable (or file) the problem is in.
+    pub key: String,
+    pub message: String,
+}
+impl ConfigError {
+    pub fn new(key: &str, message: impl Into<String>) -> Self {
+        Self { key: key.to_string(), message: message.into() }
+    }
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Parses the contents of the defaults file. Syntax errors (including duplicate keys) name the line they are on.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub fn parse_table(content: &str) -> Result<toml::Table, ConfigError> {
+    content.parse::<toml::Table>().map_err(|err| {
+        // Spans are byte offsets, report them as a line number instead.
+        let line = err.span().map(|span| content[..span.start].matches('\n').count() + 1);
+        match line {
+            Some(line) => ConfigError::new(DEFAULTS_FILE, format!("line {line}: {}", err.message())),
+            None => ConfigError::new(DEFAULTS_FILE, err.message()),
+        }
+    })
+}
+
+/// Reads a number out of a value. Quoted numbers are accepted, the same as they would be in an environment variable.
+fn number(value: &toml::Value) -> Result<f64, String> {
+    match value {
+        toml::Value::Integer(value) => Ok(*value as f64),
+        toml::Value::Float(value) => Ok(*value),
+        toml::Value::String(value) => value.trim().parse::<f64>().map_err(|_| format!("expected a number, found \"{value}\"")),
+        value => Err(format!("expected a number, found {} '{value}'", value.type_str())),
+    }
+}
+
+/// Layers the table, then environment variables of the same name, over the base config.
+/// Never fails, every problem is collected and the affected values keep their base value.
+pub fn apply(base: EnvConfig, table: &toml::Table, env: impl Fn(&str) -> Option<String>) -> (EnvConfig, Vec<ConfigError>) {
+    let mut config = base;
+    let mut errors = Vec::new();
+
+    for key in table.keys() {
+        if !EnvVariable::ALL.iter().any(|var| var.as_ref() == key) {
+            errors.push(ConfigError::new(key, "unknown variable"));
+        }
+    }
+
+    for var in EnvVariable::ALL {
+        let key = var.as_ref();
+
+        let value = match (env(key), table.get(key)) {
+            (Some(value), _) => number(&toml::Value::String(value)).map_err(|err| format!("{err} in the environment")),
+            (None, Some(value)) => number(value),
+            (None, None) => continue,
+        };
+
+        match value.and_then(|value| var.schema().check(value)) {
+            Ok(value) => config.set(var, value),
+            Err(message) => errors.push(ConfigError::new(key, message)),
+        }
+    }
+
+    (config, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(content: &str) -> (EnvConfig, Vec<ConfigError>) {
+        let table = parse_table(content).expect("the test table should parse");
+        apply(EnvConfig::default(), &table, |_| None)
+    }
+
+    #[test]
+    fn quoted_values_are_numbers() {
+        let (config, errors) = load("CHUNK_WIDTH = \"32\"\nCELL_SCALE = \" 2.5 \"");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(config.chunk_width, 32);
+        assert_eq!(config.cell_scale, 2.5);
+    }
+
+    #[test]
+    fn hash_inside_a_string_is_not_a_comment() {
+        let (config, errors) = load("CHUNK_WIDTH = \"32 # wide\"");
+        assert_eq!(config.chunk_width, EnvConfig::default().chunk_width);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key, "CHUNK_WIDTH");
+        assert!(errors[0].message.contains("32 # wide"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn trailing_comments_are_ignored() {
+        let (config, errors) = load("CHUNK_HEIGHT = 24 # (16)\nCAMERA_SPEED = 4.0# fast");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(config.chunk_height, 24);
+        assert_eq!(config.camera_speed, 4.0);
+    }
+
+    #[test]
+    fn duplicate_keys_are_rejected() {
+        let err = parse_table("CELL_SIZE = 20\nCELL_SIZE = 30").unwrap_err();
+        assert_eq!(err.key, DEFAULTS_FILE);
+        assert!(err.message.starts_with("line 2:"), "{}", err.message);
+        assert!(err.message.contains("CELL_SIZE"), "{}", err.message);
+    }
+
+    #[test]
+    fn missing_keys_keep_their_default() {
+        let (config, errors) = load("CELL_SIZE = 32");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(config, EnvConfig { cell_size: 32, ..EnvConfig::default() });
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let (config, errors) = load("CHUNK_WIDTH = 0\nCELL_SCALE = 4.5\nCAMERA_SPEED = nan");
+        assert_eq!(config, EnvConfig::default());
+        let keys: Vec<_> = errors.iter().map(|err| err.key.as_str()).collect();
+        assert_eq!(keys, ["CHUNK_WIDTH", "CELL_SCALE", "CAMERA_SPEED"]);
+        assert!(errors[0].message.contains("outside of the allowed range"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn environment_overrides_the_table() {
+        let table = parse_table("CHUNK_WIDTH = 32").unwrap();
+        let (config, errors) = apply(EnvConfig::default(), &table, |key| (key == "CHUNK_WIDTH").then(|| "64".to_string()));
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(config.chunk_width, 64);
+    }
+}
@@ -1,6 +1,6 @@
 use strum_macros::AsRefStr;
 
-#[derive(AsRefStr)]
+#[derive(AsRefStr, Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum EnvVariable {
     CHUNK_WIDTH,
@@ -8,4 +8,49 @@ pub enum EnvVariable {
     CELL_SIZE,
     CELL_SCALE,
     CAMERA_SPEED
-}
\ No newline at end of file
+}
+impl EnvVariable {
+    pub const ALL: [EnvVariable; 5] = [
+        EnvVariable::CHUNK_WIDTH,
+        EnvVariable::CHUNK_HEIGHT,
+        EnvVariable::CELL_SIZE,
+        EnvVariable::CELL_SCALE,
+        EnvVariable::CAMERA_SPEED,
+    ];
+
+    pub fn schema(&self) -> Schema {
+        use EnvVariable::*;
+        match self {
+            CHUNK_WIDTH | CHUNK_HEIGHT => Schema { default: 16.0, min: 1.0, max: 256.0, whole: true },
+            CELL_SIZE => Schema { default: 20.0, min: 1.0, max: 256.0, whole: true },
+            CELL_SCALE => Schema { default: 1.0, min: 1.0, max: 4.0, whole: false },
+            CAMERA_SPEED => Schema { default: 2.0, min: 0.5, max: 20.0, whole: false },
+        }
+    }
+}
+
+/// What a valid value looks like for a variable, and what is used when it is missing or invalid.
+#[derive(Debug, Clone, Copy)]
+pub struct Schema {
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Whether the value has to be a whole number.
+    pub whole: bool,
+}
+impl Schema {
+    /// Checks a value against the schema, describing what is wrong with it.
+    pub fn check(&self, value: f64) -> Result<f64, String> {
+        // NaN slips through every comparison below.
+        if !value.is_finite() {
+            return Err(format!("expected a number, found {value}"));
+        }
+        if self.whole && value.fract() != 0.0 {
+            return Err(format!("expected a whole number, found {value}"));
+        }
+        if value < self.min || value > self.max {
+            return Err(format!("{value} is outside of the allowed range {} to {}", self.min, self.max));
+        }
+        Ok(value)
+    }
+}
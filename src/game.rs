@@ -0,0 +1,250 @@
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::cell::{Cover, RevealCells};
+use crate::chunk_mesh::CellLooks;
+use crate::config::UserConfig;
+use crate::grid::Grid;
+use crate::history::{CellChange, History};
+use crate::input::{Action, Actions};
+
+pub struct GamePlugin;
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_state::<AppState>()
+            .add_sub_state::<GameState>()
+            .init_resource::<GameTimer>()
+            .add_message::<NewGame>()
+            .add_message::<MineHit>()
+            .add_systems(Update, (
+                // A mine hit on the first reveal loses the game rather than starting it.
+                (
+                    start_round.run_if(in_state(GameState::Ready)),
+                    handle_mine_hit.run_if(in_state(AppState::InGame)),
+                ).chain().after(RevealCells),
+                tick_timer.run_if(in_state(GameState::Playing)),
+                // A mine hit in the same move as the last safe cell still loses.
+                check_win.run_if(in_state(GameState::Playing)).after(handle_mine_hit),
+                (restart_keys, leave_game).run_if(in_state(AppState::InGame)),
+                reset_game,
+            ));
+    }
+}
+
+/// Which screen the app is showing.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    Settings,
+    Statistics,
+    InGame
+}
+
+/// The state of the current round. Only exists while InGame.
+/// A round starts as Ready, and begins Playing once the first cell is revealed.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(AppState = AppState::InGame)]
+pub enum GameState {
+    #[default]
+    Ready,
+    Playing,
+    Won,
+    Lost
+}
+impl GameState {
+    /// Whether the board should still accept input (reveals, flags).
+    pub fn is_active(&self) -> bool {
+        matches!(self, GameState::Ready | GameState::Playing)
+    }
+}
+
+/// Run condition which passes while the board should still accept input.
+pub fn game_active(state: Option<Res<State<GameState>>>) -> bool {
+    state.is_some_and(|state| state.is_active())
+}
+
+/// Time spent in the current round. Only ticks while the game is Playing.
+#[derive(Resource, Default)]
+pub struct GameTimer(pub Stopwatch);
+
+/// Sent to throw away the current board and start a fresh round.
+/// The board, camera, timer and game state are all reset.
+#[derive(Message, Default)]
+pub struct NewGame {
+    /// Seed to generate the new board from. A random seed is used when None.
+    pub seed: Option<u64>,
+}
+impl NewGame {
+    /// A brand new board.
+    pub fn fresh() -> Self {
+        Self { seed: None }
+    }
+
+    /// The same board again, generated from the given seed.
+    pub fn retry(seed: u64) -> Self {
+        Self { seed: Some(seed) }
+    }
+}
+
+/// Sent when a hidden mine is revealed. Whether that loses the game is up to the rules.
+#[derive(Message)]
+pub struct MineHit {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The first reveal starts the round.
+fn start_round(
+    grid: Res<Grid>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !grid.is_changed() { return };
+
+    if grid.iter().any(|(_, _, cell)| cell.is_revealed() || cell.exploded()) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Hitting a mine costs a life. With lives to spare, the mine is shown and flagged so it can't be hit again,
+/// and play goes on. Losing the last life loses the game.
+/// Lives are counted from the mines hit on the board, so undoing a hit gives its life back.
+/// The hit is part of the move which revealed the mine, and is undone along with it.
+fn handle_mine_hit(
+    mut reader: MessageReader<MineHit>,
+    config: Res<UserConfig>,
+    mut grid: ResMut<Grid>,
+    mut history: ResMut<History>,
+    mut looks: ResMut<CellLooks>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut lives = lives_left(&grid, &config);
+    for MineHit { x, y } in reader.read() {
+        let (x, y) = (*x, *y);
+        let Some(before) = grid.get(x, y) else { continue };
+        // A flood fill can hit the same mine more than once, and nothing is hit once the game is lost.
+        if before.exploded() || lives == 0 { continue };
+
+        let mut cell = before;
+        cell.explode();
+        lives -= 1;
+        if lives == 0 {
+            cell.set_cover(Cover::Revealed);
+            next_state.set(GameState::Lost);
+        } else {
+            cell.set_cover(Cover::Flagged);
+        }
+
+        grid.set(x, y, cell);
+        looks.animate_cell(x, y, cell, 0.0);
+        history.amend(vec![CellChange { x, y, before, after: cell }]);
+    }
+}
+
+/// Lives left in the round, counting the mines hit so far.
+pub fn lives_left(grid: &Grid, config: &UserConfig) -> u32 {
+    let hits = grid.iter().filter(|(_, _, cell)| cell.exploded()).count() as u32;
+    config.lives.saturating_sub(hits)
+}
+
+fn tick_timer(
+    time: Res<Time>,
+    mut timer: ResMut<GameTimer>,
+) {
+    timer.0.tick(time.delta());
+}
+
+/// The game is won once every air cell has been revealed.
+fn check_win(
+    grid: Res<Grid>,
+    config: Res<UserConfig>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Only recheck the board when something was revealed.
+    if !grid.is_changed() || lives_left(&grid, &config) == 0 { return };
+
+    if grid.iter().all(|(_, _, cell)| !cell.is_air() || cell.is_revealed()) {
+        next_state.set(GameState::Won);
+    }
+}
+
+fn restart_keys(
+    actions: Actions,
+    grid: Res<Grid>,
+    mut writer: MessageWriter<NewGame>,
+) {
+    if actions.just_pressed(Action::Retry) {
+        writer.write(NewGame::retry(grid.seed()));
+    } else if actions.just_pressed(Action::Restart) {
+        writer.write(NewGame::fresh());
+    }
+}
+
+/// Leaves the board and goes back to the main menu.
+fn leave_game(
+    actions: Actions,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if actions.just_pressed(Action::Menu) {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn reset_game(
+    mut reader: MessageReader<NewGame>,
+    mut timer: ResMut<GameTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if reader.read().count() == 0 { return };
+
+    timer.0.reset();
+    next_state.set(GameState::Ready);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use crate::cell::CellState;
+    use crate::layout::CellType;
+
+    use super::*;
+
+    #[test]
+    fn hitting_a_mine_with_the_last_safe_cell_loses() {
+        let mut app = App::new();
+        app
+            .add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .add_sub_state::<GameState>()
+            .add_message::<MineHit>()
+            .init_resource::<UserConfig>()
+            .init_resource::<History>()
+            .init_resource::<CellLooks>()
+            .insert_resource(Grid::default())
+            .add_systems(Update, (check_win.run_if(in_state(GameState::Playing)).after(handle_mine_hit), handle_mine_hit));
+
+        app.world_mut().resource_mut::<NextState<AppState>>().set(AppState::InGame);
+        app.update();
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+
+        // Every safe cell is revealed, along with the one mine on the board.
+        let mut grid = app.world_mut().resource_mut::<Grid>();
+        let cells: Vec<(i32, i32, CellState)> = grid.iter().collect();
+        for (x, y, mut cell) in cells {
+            if !cell.is_air() { continue };
+            if (x, y) == (1, 1) {
+                cell = CellState::new(CellType::Mine);
+            } else {
+                cell.set_cover(Cover::Revealed);
+            }
+            grid.set(x, y, cell);
+        }
+        app.world_mut().write_message(MineHit { x: 1, y: 1 });
+        app.update();
+        app.update();
+
+        assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Lost);
+    }
+}
@@ -1,37 +1,32 @@
-use bevy::{platform::collections::HashMap, prelude::*};
-use std::sync::LazyLock;
+use bevy::prelude::*;
 
-use crate::env::{EnvVariable, acquire_num};
-
-static CHUNK_WIDTH: LazyLock<usize> = LazyLock::new(|| acquire_num(EnvVariable::CHUNK_WIDTH));
-static CHUNK_HEIGHT: LazyLock<usize> = LazyLock::new(|| acquire_num(EnvVariable::CHUNK_HEIGHT));
+use crate::cell::CellState;
+use crate::config::BoardSettings;
+use crate::settings::Settings;
 
+/// A rectangle of cells, stored row by row.
 pub struct Chunk {
-    // cells: [Option<Entity>; CHUNK_WIDTH * CHUNK_HEIGHT]
-    cells: Vec<Option<Entity>>
+    /// Width of the chunk in cells.
+    width: u32,
+    // cells: [CellState; width * height]
+    cells: Vec<CellState>
 }
 impl Chunk {
     fn index(&self, x: u32, y: u32) -> usize {
-        (y as usize * *CHUNK_WIDTH) + x as usize
+        (y as usize * self.width as usize) + x as usize
     }
 
-    pub fn new() -> Self {
-        Self { cells: vec![None; *CHUNK_WIDTH * *CHUNK_HEIGHT] }
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, cells: vec![CellState::default(); width as usize * height as usize] }
     }
 
-    pub fn get(&self, x: u32, y: u32) -> Option<Entity> {
+    pub fn get(&self, x: u32, y: u32) -> CellState {
         self.cells[self.index(x, y)]
     }
 
-    pub fn insert(&mut self, x: u32, y: u32, entity: Entity) {
+    pub fn set(&mut self, x: u32, y: u32, state: CellState) {
         let i = self.index(x, y);
-        self.cells[i] = Some(entity);
-    }
-
-    #[allow(dead_code)]
-    pub fn delete(&mut self, x: u32, y: u32) {
-        let i = self.index(x, y);
-        self.cells[i] = None;
+        self.cells[i] = state;
     }
 }
 
@@ -41,46 +36,101 @@ pub struct Grid {
     width: u32,
     /// Height in cells
     height: u32,
+    /// Number of mines placed on the board
+    mines: u32,
+    /// Number of walls placed inside of the border
+    walls: u32,
     // The size of a full cell. The scaling is included in this number.
     cell_size: u32,
     // How much every cell is scaled up from it's original texture.
     scale: f32,
-    chunks: HashMap<(i32, i32), Chunk>
+    /// Seed the current board was generated from.
+    seed: u64,
+    /// Proportions of each chunk in cells, fixed for the lifetime of the grid.
+    chunk_width: u32,
+    chunk_height: u32,
+    /// Number of chunks across the grid.
+    chunks_across: u32,
+    /// Every chunk of the grid, row by row.
+    chunks: Vec<Chunk>
 }
 impl Grid {
+    /// Creates a grid of hidden air cells for the given board. The grid is one cell larger on every side to fit the wall border.
+    pub fn new(board: &BoardSettings, settings: &Settings) -> Self {
+        let (width, height) = (board.width + 2, board.height + 2);
+        let chunks_across = width.div_ceil(settings.chunk_width);
+        let chunks_down = height.div_ceil(settings.chunk_height);
+
+        Self {
+            width,
+            height,
+            mines: board.mines,
+            walls: board.walls,
+            cell_size: settings.scaled_cell_size(),
+            scale: settings.cell_scale,
+            seed: 0,
+            chunk_width: settings.chunk_width,
+            chunk_height: settings.chunk_height,
+            chunks_across,
+            chunks: (0..chunks_across * chunks_down).map(|_| Chunk::new(settings.chunk_width, settings.chunk_height)).collect()
+        }
+    }
+
     /** Getters */
     pub fn width(&self) -> u32 { self.width }
     pub fn height(&self) -> u32 { self.height }
+    pub fn mines(&self) -> u32 { self.mines }
+    pub fn walls(&self) -> u32 { self.walls }
     pub fn cell_size(&self) -> u32 { self.cell_size }
     pub fn scale(&self) -> f32 { self.scale }
+    pub fn seed(&self) -> u64 { self.seed }
+    pub fn chunk_width(&self) -> u32 { self.chunk_width }
+    pub fn chunk_height(&self) -> u32 { self.chunk_height }
+
+    pub fn set_seed(&mut self, seed: u64) { self.seed = seed; }
+
+    /// Changes the size of every cell. The cells themselves must be moved separately.
+    pub fn set_scale(&mut self, settings: &Settings) {
+        self.cell_size = settings.scaled_cell_size();
+        self.scale = settings.cell_scale;
+    }
 
-    pub fn get(&self, x: i32, y: i32) -> Option<Entity> {
-        let cx = x / *CHUNK_WIDTH as i32;
-        let cy = y / *CHUNK_HEIGHT as i32;
-
-        if !self.chunks.contains_key(&(cx, cy)) { panic!("Attempted to reference a chunk which does not exist.") };
-        
-        // Local (x, y) within the chunk.
-        let lx = x.rem_euclid(*CHUNK_WIDTH as i32) as u32;
-        let ly = y.rem_euclid(*CHUNK_HEIGHT as i32) as u32;
-        
-        self.chunks.get(&(cx, cy)).unwrap().get(lx, ly)
+    /// Whether the position is on the board.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
     }
 
-    pub fn insert(&mut self, x: i32, y: i32, entity: Entity) {
-        let cx = x / *CHUNK_WIDTH as i32;
-        let cy = y / *CHUNK_HEIGHT as i32;
+    /// The chunk holding the position, and the position within that chunk.
+    fn locate(&self, x: i32, y: i32) -> (usize, u32, u32) {
+        let (x, y) = (x as u32, y as u32);
+        let chunk = (y / self.chunk_height) * self.chunks_across + x / self.chunk_width;
+        (chunk as usize, x % self.chunk_width, y % self.chunk_height)
+    }
 
-        if !self.chunks.contains_key(&(cx, cy)) { 
-            self.chunks.insert((cx, cy), Chunk::new());
-        }
+    /// The state of the cell at the position, or None when it is off the board.
+    pub fn get(&self, x: i32, y: i32) -> Option<CellState> {
+        if !self.contains(x, y) { return None };
 
-        // Local (x, y) within the chunk.
-        let lx = x.rem_euclid(*CHUNK_WIDTH as i32) as u32;
-        let ly = y.rem_euclid(*CHUNK_HEIGHT as i32) as u32;
+        let (chunk, lx, ly) = self.locate(x, y);
+        Some(self.chunks[chunk].get(lx, ly))
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, state: CellState) {
+        if !self.contains(x, y) { panic!("Attempted to set a cell outside of the grid.") };
+
+        let (chunk, lx, ly) = self.locate(x, y);
+        self.chunks[chunk].set(lx, ly, state);
+    }
 
-        let chunk = self.chunks.get_mut(&(cx, cy)).unwrap();
-        chunk.insert(lx, ly, entity);
+    /// Iterates the positions of the (up to 8) cells around the position which are on the board.
+    pub fn neighbors(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |&(nx, ny)| (nx, ny) != (x, y) && self.contains(nx, ny))
+    }
+
+    /// Iterates the (x, y, state) of every cell in the grid, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        (0..self.height as i32).flat_map(move |y| (0..self.width as i32).map(move |x| (x, y, self.get(x, y).unwrap())))
     }
 
     pub fn pos_from_world(&self, pos: Vec2) -> Vec2 {
@@ -93,19 +143,8 @@ impl Grid {
     
 }
 
-const CELL_SIZE: LazyLock<u32> = LazyLock::new(|| acquire_num(EnvVariable::CELL_SIZE));
-const CELL_SCALE: LazyLock<f32> = LazyLock::new(|| acquire_num(EnvVariable::CELL_SCALE));
 impl Default for Grid {
     fn default() -> Self {
-        let width = 16;
-        let height = 16;
-
-         Self {
-            width,
-            height,
-            cell_size: (*CELL_SIZE as f32 * *CELL_SCALE).floor() as u32,
-            scale: *CELL_SCALE,
-            chunks: HashMap::new()
-        }
+        Self::new(&BoardSettings::default(), &Settings::default())
     }
 }
\ No newline at end of file
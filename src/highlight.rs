@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::camera::MouseDrag;
+use crate::cell::{BoardChanged, get_cursor_position};
+use crate::chunk_mesh::CellLooks;
+use crate::game::{AppState, GameState, game_active};
+use crate::grid::Grid;
+use crate::hud::cursor_over_ui;
+use crate::input::{Action, Actions};
+
+pub struct HighlightPlugin;
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HoveredCell>()
+            .init_resource::<PressedCells>()
+            .add_systems(OnEnter(AppState::InGame), spawn_hover)
+            .add_systems(Update, (
+                hover_cell,
+                (draw_hover, press_cells),
+            ).chain().run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Height of the hover highlight, above the board and below the cell cursor.
+const HOVER_Z: f32 = 1.5;
+const HOVER_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.12);
+
+/// The cell under the mouse, when the board can be played and the mouse is not over the UI.
+#[derive(Resource, Default)]
+struct HoveredCell(Option<IVec2>);
+
+/// Cells currently drawn as held down, so they can be let go once the button is released.
+#[derive(Resource, Default)]
+struct PressedCells(Vec<IVec2>);
+
+/// Marks the sprite highlighting the cell under the mouse.
+#[derive(Component)]
+struct HoverSprite;
+
+fn spawn_hover(mut commands: Commands) {
+    commands.spawn((
+        Sprite::from_color(HOVER_COLOR, Vec2::ONE),
+        Transform::from_xyz(0.0, 0.0, HOVER_Z),
+        Visibility::Hidden,
+        HoverSprite,
+        DespawnOnExit(AppState::InGame),
+    ));
+}
+
+/// Finds the cell under the mouse. Nothing is hovered while panning, over the UI, or once the round is over.
+fn hover_cell(
+    grid: Res<Grid>,
+    drag: Res<MouseDrag>,
+    state: Option<Res<State<GameState>>>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    interactions: Query<&Interaction>,
+    mut hovered: ResMut<HoveredCell>,
+) {
+    let cell = get_cursor_position(windows, camera_q)
+        .map(|world_pos| grid.cell_from_world(world_pos))
+        .filter(|cell| grid.contains(cell.x, cell.y))
+        .filter(|_| game_active(state) && !drag.panned() && !cursor_over_ui(interactions));
+    if hovered.0 != cell {
+        hovered.0 = cell;
+    }
+}
+
+/// Highlights the hovered cell.
+fn draw_hover(
+    grid: Res<Grid>,
+    hovered: Res<HoveredCell>,
+    mut sprite: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<HoverSprite>>,
+) {
+    let Ok((mut sprite, mut transform, mut visibility)) = sprite.single_mut() else { return };
+    let Some(cell) = hovered.0 else {
+        if *visibility != Visibility::Hidden { *visibility = Visibility::Hidden };
+        return;
+    };
+
+    let cell_size = grid.cell_size() as f32;
+    sprite.custom_size = Some(Vec2::splat(cell_size));
+    transform.translation = (cell.as_vec2() * cell_size).extend(HOVER_Z);
+    *visibility = Visibility::Visible;
+}
+
+/// Shows the hidden cells under the mouse as held down while a reveal is held, or the whole 3x3 neighbourhood
+/// while a chord is held, as in classic minesweeper. The cells are let go as soon as the button is released.
+fn press_cells(
+    grid: Res<Grid>,
+    actions: Actions,
+    hovered: Res<HoveredCell>,
+    mut board_changed: MessageReader<BoardChanged>,
+    mut pressed: ResMut<PressedCells>,
+    mut looks: ResMut<CellLooks>,
+) {
+    // A new board starts with every cell let go.
+    if board_changed.read().count() > 0 {
+        pressed.0.clear();
+    }
+
+    let cells: Vec<IVec2> = match hovered.0 {
+        Some(cell) if actions.mouse_pressed(Action::Chord) => {
+            grid.neighbors(cell.x, cell.y).map(|(x, y)| IVec2::new(x, y)).chain([cell]).collect()
+        },
+        Some(cell) if actions.mouse_pressed(Action::Reveal) => vec![cell],
+        _ => Vec::new(),
+    };
+    // Only cells which could be revealed are pushed in, flags and revealed cells stay as they are.
+    let cells: Vec<IVec2> = cells.into_iter()
+        .filter(|cell| grid.get(cell.x, cell.y).is_some_and(|state| state.can_reveal()))
+        .collect();
+    if pressed.0 == cells { return };
+
+    for cell in pressed.0.iter().filter(|cell| !cells.contains(cell)) {
+        looks.press(cell.x, cell.y, false);
+    }
+    for cell in cells.iter() {
+        looks.press(cell.x, cell.y, true);
+    }
+    pressed.0 = cells;
+}
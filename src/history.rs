@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+
+use crate::cell::CellState;
+use crate::chunk_mesh::CellLooks;
+use crate::config::UserConfig;
+use crate::game::{AppState, GameState, NewGame};
+use crate::grid::Grid;
+use crate::input::{Action, Actions};
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<History>()
+            .add_systems(Update, (
+                forget_history,
+                undo_redo.run_if(in_state(AppState::InGame)),
+            ).chain());
+    }
+}
+
+/// A single cell changed by a move.
+#[derive(Clone, Copy, Debug)]
+pub struct CellChange {
+    pub x: i32,
+    pub y: i32,
+    pub before: CellState,
+    pub after: CellState,
+}
+
+/// Every move made on the current board, as the cells each one changed, so they can be undone and redone.
+/// A move is whatever changed the board in one go: a flag, or a reveal with everything its flood fill (or chord) uncovered.
+#[derive(Resource, Default)]
+pub struct History {
+    undo: Vec<Vec<CellChange>>,
+    redo: Vec<Vec<CellChange>>,
+}
+impl History {
+    /// Records a new move. Anything undone until now can no longer be redone.
+    pub fn record(&mut self, changes: Vec<CellChange>) {
+        if changes.is_empty() { return };
+
+        self.begin(changes);
+    }
+
+    /// Records a new move, even one which changed nothing yet, so the changes following from it can be amended to it.
+    pub fn begin(&mut self, changes: Vec<CellChange>) {
+        self.undo.push(changes);
+        self.redo.clear();
+    }
+
+    /// Adds changes which followed from the last move (such as the mines shown once the game is lost) to it,
+    /// so they are undone along with it.
+    pub fn amend(&mut self, changes: Vec<CellChange>) {
+        match self.undo.last_mut() {
+            Some(last) => last.extend(changes),
+            None => self.record(changes),
+        }
+    }
+}
+
+/// The states the cells of a move are set to, in order, to undo or redo it.
+/// Later changes to a cell take precedence, so they are undone first.
+fn replay(changes: &[CellChange], undoing: bool) -> Vec<(i32, i32, CellState)> {
+    if undoing {
+        changes.iter().rev().map(|change| (change.x, change.y, change.before)).collect()
+    } else {
+        changes.iter().map(|change| (change.x, change.y, change.after)).collect()
+    }
+}
+
+/// A new board starts with an empty history.
+fn forget_history(
+    mut reader: MessageReader<NewGame>,
+    mut history: ResMut<History>,
+) {
+    if reader.read().count() == 0 { return };
+
+    history.undo.clear();
+    history.redo.clear();
+}
+
+/// Undoes the last move, or redoes the last undone one, animating every cell it changed.
+/// Moves can only be taken back while the round is being played, or after losing in practice mode,
+/// which puts the round back in play.
+fn undo_redo(
+    actions: Actions,
+    config: Res<UserConfig>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut history: ResMut<History>,
+    mut grid: ResMut<Grid>,
+    mut looks: ResMut<CellLooks>,
+) {
+    let allowed = state.is_active() || (*state.get() == GameState::Lost && config.practice);
+    if !allowed { return };
+
+    let history = history.as_mut();
+    let (from, to, undoing) = if actions.just_pressed(Action::Undo) {
+        (&mut history.undo, &mut history.redo, true)
+    } else if actions.just_pressed(Action::Redo) {
+        (&mut history.redo, &mut history.undo, false)
+    } else {
+        return;
+    };
+    let Some(changes) = from.pop() else { return };
+
+    // Hitting a mine can only be taken back in practice mode, even with lives to spare.
+    let hit = changes.iter().any(|change| change.after.exploded() && !change.before.exploded());
+    if undoing && hit && !config.practice {
+        from.push(changes);
+        return;
+    }
+
+    for (x, y, cell) in replay(&changes, undoing) {
+        grid.set(x, y, cell);
+        looks.animate_cell(x, y, cell, 0.0);
+    }
+
+    // Mines hit with lives to spare stay flagged, only the last one is revealed.
+    let lost = changes.iter().any(|change| change.after.exploded() && change.after.is_revealed());
+    if lost && undoing {
+        // Flags which were crossed out when the game was lost are plain flags again.
+        for (x, y, cell) in grid.iter().filter(|(_, _, cell)| cell.is_flagged()) {
+            looks.set_cell(x, y, cell);
+        }
+        next_state.set(GameState::Playing);
+    } else if lost {
+        next_state.set(GameState::Lost);
+    }
+
+    to.push(changes);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::Cover;
+    use crate::layout::CellType;
+
+    use super::*;
+
+    fn cell(cover: Cover) -> CellState {
+        let mut cell = CellState::new(CellType::Air(1));
+        cell.set_cover(cover);
+        cell
+    }
+
+    fn change(x: i32, before: Cover, after: Cover) -> CellChange {
+        CellChange { x, y: 1, before: cell(before), after: cell(after) }
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::default();
+        history.record(vec![change(1, Cover::Hidden, Cover::Flagged)]);
+        history.redo.push(history.undo.pop().unwrap());
+
+        // Nothing changed, so there is nothing to forget.
+        history.record(Vec::new());
+        assert_eq!(history.redo.len(), 1);
+
+        history.record(vec![change(2, Cover::Hidden, Cover::Revealed)]);
+        assert_eq!(history.undo.len(), 1);
+        assert!(history.redo.is_empty());
+    }
+
+    #[test]
+    fn amending_follows_an_empty_move() {
+        let mut history = History::default();
+        history.record(vec![change(1, Cover::Hidden, Cover::Flagged)]);
+        history.begin(Vec::new());
+        history.amend(vec![change(2, Cover::Hidden, Cover::Revealed)]);
+        assert_eq!(history.undo.len(), 2);
+        assert_eq!(history.undo[1].len(), 1);
+    }
+
+    #[test]
+    fn amending_nothing_records_a_move() {
+        let mut history = History::default();
+        history.amend(vec![change(1, Cover::Hidden, Cover::Revealed)]);
+        assert_eq!(history.undo.len(), 1);
+
+        history.amend(vec![change(2, Cover::Hidden, Cover::Revealed)]);
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].len(), 2);
+    }
+
+    #[test]
+    fn undo_restores_the_state_before_the_move() {
+        let changes = [
+            change(1, Cover::Hidden, Cover::Flagged),
+            change(2, Cover::Hidden, Cover::Revealed),
+            change(1, Cover::Flagged, Cover::Questioned),
+        ];
+
+        let mut grid = Grid::default();
+        for (x, y, state) in replay(&changes, false) {
+            grid.set(x, y, state);
+        }
+        assert_eq!(grid.get(1, 1), Some(cell(Cover::Questioned)));
+        assert_eq!(grid.get(2, 1), Some(cell(Cover::Revealed)));
+
+        let undone = replay(&changes, true);
+        assert_eq!(undone.iter().map(|(x, _, _)| *x).collect::<Vec<_>>(), [1, 2, 1]);
+        for (x, y, state) in undone {
+            grid.set(x, y, state);
+        }
+        assert_eq!(grid.get(1, 1), Some(cell(Cover::Hidden)));
+        assert_eq!(grid.get(2, 1), Some(cell(Cover::Hidden)));
+    }
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+
+use crate::config::UserConfig;
+use crate::game::{AppState, GameState, GameTimer, NewGame, lives_left};
+use crate::grid::Grid;
+
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), spawn_hud)
+            .add_systems(Update, (
+                update_mine_counter,
+                update_lives,
+                update_timer,
+                update_face,
+                reset_button,
+            ).run_if(in_state(AppState::InGame)));
+    }
+}
+
+pub const HUD_HEIGHT: f32 = 48.0;
+const HUD_FONT_SIZE: f32 = 28.0;
+const HUD_BACKGROUND: Color = Color::srgb(0.12, 0.12, 0.14);
+const BUTTON_IDLE: Color = Color::srgb(0.25, 0.25, 0.28);
+const BUTTON_HOVERED: Color = Color::srgb(0.35, 0.35, 0.38);
+const BUTTON_PRESSED: Color = Color::srgb(0.18, 0.18, 0.2);
+
+/// Marks the text displaying how many mines are left unflagged.
+#[derive(Component)]
+struct MineCounter;
+
+/// Marks the text displaying the lives left, in lives mode.
+#[derive(Component)]
+struct LivesText;
+
+/// Marks the text displaying the elapsed time of the round.
+#[derive(Component)]
+struct TimerText;
+
+/// Marks the reset button. Its label shows the current game state.
+#[derive(Component)]
+struct FaceButton;
+
+/// Marks the text inside of the reset button.
+#[derive(Component)]
+struct FaceText;
+
+/// Run condition which passes while the cursor is over any interactable UI node.
+/// Used to keep clicks on the HUD from reaching the board below it.
+pub fn cursor_over_ui(interactions: Query<&Interaction>) -> bool {
+    interactions.iter().any(|interaction| *interaction != Interaction::None)
+}
+
+fn face(state: &GameState) -> &'static str {
+    match state {
+        GameState::Ready | GameState::Playing => ":)",
+        GameState::Won => "B)",
+        GameState::Lost => "X(",
+    }
+}
+
+fn spawn_hud(
+    mut commands: Commands
+) {
+    let text_font = TextFont::from_font_size(HUD_FONT_SIZE);
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Px(HUD_HEIGHT),
+            padding: UiRect::horizontal(Val::Px(16.0)),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(HUD_BACKGROUND),
+        DespawnOnExit(AppState::InGame),
+        // Lets the HUD bar itself block clicks, not just the button.
+        Interaction::default(),
+        children![
+            (
+                Node {
+                    column_gap: Val::Px(12.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        Text::new("000"),
+                        text_font.clone(),
+                        TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                        MineCounter,
+                    ),
+                    (
+                        Text::default(),
+                        TextFont::from_font_size(HUD_FONT_SIZE * 0.6),
+                        TextColor(Color::srgb(0.95, 0.85, 0.2)),
+                        LivesText,
+                    ),
+                ],
+            ),
+            (
+                Button,
+                Node {
+                    width: Val::Px(HUD_HEIGHT - 8.0),
+                    height: Val::Px(HUD_HEIGHT - 8.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(BUTTON_IDLE),
+                FaceButton,
+                children![(
+                    Text::new(face(&GameState::default())),
+                    text_font.clone(),
+                    TextColor(Color::srgb(0.95, 0.85, 0.2)),
+                    FaceText,
+                )],
+            ),
+            (
+                Text::new("000"),
+                text_font,
+                TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                TimerText,
+            ),
+        ],
+    ));
+}
+
+/// Remaining mines are the total mines minus the number of flags placed.
+/// This goes negative when the player places more flags than there are mines.
+fn update_mine_counter(
+    grid: Res<Grid>,
+    mut text: Single<&mut Text, With<MineCounter>>,
+) {
+    if !grid.is_changed() && !text.is_added() { return };
+
+    let mines = grid.iter().filter(|(_, _, cell)| cell.is_mine()).count();
+    let flags = grid.iter().filter(|(_, _, cell)| cell.is_flagged()).count();
+    let remaining = mines as i32 - flags as i32;
+    let value = format!("{:03}", remaining);
+    if text.0 != value {
+        text.0 = value;
+    }
+}
+
+/// Shows the lives left, unless the game is played with a single one.
+fn update_lives(
+    grid: Res<Grid>,
+    config: Res<UserConfig>,
+    mut text: Single<&mut Text, With<LivesText>>,
+) {
+    if !grid.is_changed() && !config.is_changed() && !text.is_added() { return };
+
+    let value = if config.lives > 1 { format!("Lives {}", lives_left(&grid, &config)) } else { String::new() };
+    if text.0 != value {
+        text.0 = value;
+    }
+}
+
+fn update_timer(
+    timer: Res<GameTimer>,
+    mut text: Single<&mut Text, With<TimerText>>,
+) {
+    let seconds = (timer.0.elapsed_secs() as u32).min(999);
+    let value = format!("{:03}", seconds);
+    if text.0 != value {
+        text.0 = value;
+    }
+}
+
+fn update_face(
+    state: Res<State<GameState>>,
+    mut text: Single<&mut Text, With<FaceText>>,
+) {
+    if !state.is_changed() && !text.is_added() { return };
+    text.0 = face(state.get()).to_string();
+}
+
+/// Colours the reset button by its interaction, and starts a new game when it is pressed.
+fn reset_button(
+    mut writer: MessageWriter<NewGame>,
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<FaceButton>)>,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                background.0 = BUTTON_PRESSED;
+                writer.write(NewGame::fresh());
+            },
+            Interaction::Hovered => background.0 = BUTTON_HOVERED,
+            Interaction::None => background.0 = BUTTON_IDLE,
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::user_dir;
+
+const BINDINGS_FILE: &str = "bindings.toml";
+/// How far a stick has to be pushed before it counts as pressed.
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct InputPlugin;
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        // A map inserted beforehand is kept, which lets tests stay clear of the user's bindings file.
+        if !app.world().contains_resource::<InputMap>() {
+            app.insert_resource(InputMap::load());
+        }
+    }
+}
+
+/// Everything the player can do on the board. Each action is bound to any number of keys or buttons.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Reveal,
+    Flag,
+    /// Reveals the neighbors of a number once all of its mines are flagged.
+    Chord,
+    /// Moves the keyboard cursor one cell.
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    /// Drags the camera along with the cursor while held.
+    PanDrag,
+    ZoomIn,
+    ZoomOut,
+    /// Centres the camera on the board, zoomed to fit all of it.
+    FitBoard,
+    /// Starts a new board.
+    Restart,
+    /// Starts the current board over again.
+    Retry,
+    /// Takes back the last move.
+    Undo,
+    /// Makes the last move taken back again.
+    Redo,
+    /// Leaves the board for the main menu.
+    Menu,
+}
+impl Action {
+    /// Actions which happen on a cell. When bound to a mouse button they fire on release at the mouse,
+    /// so a press which turns into a camera drag doesn't also count as a click. Keys and gamepad buttons fire on press at the cell cursor.
+    pub fn is_click(&self) -> bool {
+        matches!(self, Action::Reveal | Action::Flag | Action::Chord)
+    }
+}
+
+/// A single key, button or stick direction an action can be bound to.
+/// Gamepad bindings are shared by every connected gamepad.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+    /// Pressed while the axis is pushed past STICK_THRESHOLD in the given direction.
+    /// Sticks are only ever held, they never count as just pressed or released.
+    Stick(GamepadAxis, AxisDirection),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// Maps every action to the inputs which trigger it.
+/// Loaded from the user's bindings file, any action missing from the file keeps its default bindings.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>
+}
+impl InputMap {
+    fn path() -> PathBuf {
+        user_dir().join(BINDINGS_FILE)
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether the mouse button triggers any of the click actions.
+    pub fn is_click_button(&self, button: MouseButton) -> bool {
+        self.bindings.iter().any(|(action, bindings)| action.is_click() && bindings.contains(&Binding::Mouse(button)))
+    }
+
+    /// Loads the bindings file. When it doesn't exist yet, it is written with the defaults so it can be edited.
+    pub fn load() -> Self {
+        let mut map = Self::default();
+
+        let Ok(content) = fs::read_to_string(Self::path()) else {
+            if let Err(err) = map.save() {
+                warn!("Failed to write default bindings file '{}': {}", Self::path().display(), err);
+            }
+            return map;
+        };
+
+        match Self::parse(&content) {
+            Ok(loaded) => map.bindings.extend(loaded.bindings),
+            Err(err) => warn!("Ignoring malformed bindings file '{}': {}", Self::path().display(), err),
+        }
+        map
+    }
+
+    /// Reads the bindings given in a bindings file. Actions which no longer exist are skipped, rather than rejecting the whole file.
+    fn parse(content: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct File {
+            bindings: toml::Table,
+        }
+
+        let file: File = toml::from_str(content).map_err(|err| err.message().to_string())?;
+        let mut bindings = BTreeMap::new();
+        for (name, value) in file.bindings {
+            let Ok(action) = toml::Value::String(name.clone()).try_into::<Action>() else {
+                warn!("Ignoring unknown action '{name}' in bindings file '{}'", Self::path().display());
+                continue;
+            };
+            let value = value.try_into::<Vec<Binding>>().map_err(|err| format!("{name}: {}", err.message()))?;
+            bindings.insert(action, value);
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let content = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::create_dir_all(user_dir())?;
+        fs::write(Self::path(), content)
+    }
+}
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::*;
+        use AxisDirection::*;
+
+        let bindings = BTreeMap::from([
+            (Action::Reveal, vec![Mouse(MouseButton::Left), Key(KeyCode::Enter), Key(KeyCode::NumpadEnter), Gamepad(GamepadButton::South)]),
+            (Action::Flag, vec![Mouse(MouseButton::Right), Key(KeyCode::KeyE), Gamepad(GamepadButton::East)]),
+            (Action::Chord, vec![Mouse(MouseButton::Middle), Key(KeyCode::Space), Gamepad(GamepadButton::West)]),
+            (Action::CursorUp, vec![Key(KeyCode::ArrowUp), Gamepad(GamepadButton::DPadUp)]),
+            (Action::CursorDown, vec![Key(KeyCode::ArrowDown), Gamepad(GamepadButton::DPadDown)]),
+            (Action::CursorLeft, vec![Key(KeyCode::ArrowLeft), Gamepad(GamepadButton::DPadLeft)]),
+            (Action::CursorRight, vec![Key(KeyCode::ArrowRight), Gamepad(GamepadButton::DPadRight)]),
+            (Action::PanUp, vec![Key(KeyCode::KeyW), Stick(GamepadAxis::LeftStickY, Positive)]),
+            (Action::PanDown, vec![Key(KeyCode::KeyS), Stick(GamepadAxis::LeftStickY, Negative)]),
+            (Action::PanLeft, vec![Key(KeyCode::KeyA), Stick(GamepadAxis::LeftStickX, Negative)]),
+            (Action::PanRight, vec![Key(KeyCode::KeyD), Stick(GamepadAxis::LeftStickX, Positive)]),
+            (Action::PanDrag, vec![Mouse(MouseButton::Middle), Mouse(MouseButton::Right)]),
+            (Action::ZoomIn, vec![Key(KeyCode::Equal), Key(KeyCode::NumpadAdd), Gamepad(GamepadButton::RightTrigger)]),
+            (Action::ZoomOut, vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract), Gamepad(GamepadButton::LeftTrigger)]),
+            (Action::FitBoard, vec![Key(KeyCode::KeyF), Gamepad(GamepadButton::North)]),
+            (Action::Restart, vec![Key(KeyCode::F2), Key(KeyCode::KeyR), Gamepad(GamepadButton::Start)]),
+            (Action::Retry, vec![Key(KeyCode::F3)]),
+            (Action::Undo, vec![Key(KeyCode::KeyZ)]),
+            (Action::Redo, vec![Key(KeyCode::KeyY)]),
+            (Action::Menu, vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Select)]),
+        ]);
+
+        Self { bindings }
+    }
+}
+
+/// Reads the state of actions through the input map, instead of raw keys and buttons.
+#[derive(SystemParam)]
+pub struct Actions<'w, 's> {
+    map: Res<'w, InputMap>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+}
+impl Actions<'_, '_> {
+    fn any(&self, action: Action, check: impl Fn(&Binding) -> bool) -> bool {
+        self.map.bindings(action).iter().any(check)
+    }
+
+    fn stick_pushed(&self, axis: GamepadAxis, direction: AxisDirection) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            let value = gamepad.get(axis).unwrap_or(0.0);
+            match direction {
+                AxisDirection::Positive => value > STICK_THRESHOLD,
+                AxisDirection::Negative => value < -STICK_THRESHOLD,
+            }
+        })
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(action, |binding| match binding {
+            Binding::Key(key) => self.keyboard.pressed(*key),
+            Binding::Mouse(button) => self.mouse.pressed(*button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+            Binding::Stick(axis, direction) => self.stick_pushed(*axis, *direction),
+        })
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(action, |binding| match binding {
+            Binding::Key(key) => self.keyboard.just_pressed(*key),
+            Binding::Mouse(button) => self.mouse.just_pressed(*button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+            Binding::Stick(..) => false,
+        })
+    }
+
+    /// Whether one of the action's keys or gamepad buttons was pressed this frame, ignoring its mouse buttons.
+    /// These act on the cell cursor rather than under the mouse.
+    pub fn cursor_just_pressed(&self, action: Action) -> bool {
+        self.any(action, |binding| match binding {
+            Binding::Key(key) => self.keyboard.just_pressed(*key),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+            _ => false,
+        })
+    }
+
+    /// Whether one of the action's mouse buttons was clicked this frame, ignoring its keys.
+    /// Clicks fire on release (see [`Action::is_click`]).
+    pub fn clicked(&self, action: Action) -> bool {
+        self.any(action, |binding| matches!(binding, Binding::Mouse(button) if self.mouse.just_released(*button)))
+    }
+
+    /// Whether one of the action's mouse buttons is held down, ignoring its keys.
+    pub fn mouse_pressed(&self, action: Action) -> bool {
+        self.mouse_buttons(action).any(|button| self.mouse.pressed(button))
+    }
+
+    /// Mouse buttons bound to the given action.
+    pub fn mouse_buttons(&self, action: Action) -> impl Iterator<Item = MouseButton> + '_ {
+        self.map.bindings(action).iter().filter_map(|binding| match binding {
+            Binding::Mouse(button) => Some(*button),
+            _ => None
+        })
+    }
+
+    pub fn is_click_button(&self, button: MouseButton) -> bool {
+        self.map.is_click_button(button)
+    }
+
+    pub fn mouse(&self) -> &ButtonInput<MouseButton> {
+        &self.mouse
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bevy::input::{
+        InputPlugin as BevyInputPlugin,
+        gamepad::{GamepadConnection, GamepadConnectionEvent, RawGamepadAxisChangedEvent, RawGamepadButtonChangedEvent, RawGamepadEvent},
+    };
+
+    use super::*;
+
+    /// An app reading input through the default bindings, without touching the user's bindings file.
+    pub(crate) fn test_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins(BevyInputPlugin)
+            .insert_resource(InputMap::default())
+            .add_plugins(InputPlugin);
+        app
+    }
+
+    /// Connects a gamepad, ready to take input from the next update on.
+    pub(crate) fn connect_gamepad(app: &mut App) -> Entity {
+        let gamepad = app.world_mut().spawn_empty().id();
+        app.world_mut().write_message(GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected {
+            name: "Test gamepad".to_string(),
+            vendor_id: None,
+            product_id: None,
+        }));
+        app.update();
+        gamepad
+    }
+
+    /// Sets how far a button is pushed in, from 0 (released) to 1.
+    pub(crate) fn set_button(app: &mut App, gamepad: Entity, button: GamepadButton, value: f32) {
+        app.world_mut().write_message(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(gamepad, button, value)));
+    }
+
+    pub(crate) fn set_axis(app: &mut App, gamepad: Entity, axis: GamepadAxis, value: f32) {
+        app.world_mut().write_message(RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(gamepad, axis, value)));
+    }
+
+    #[test]
+    fn unknown_actions_are_skipped() {
+        let map = InputMap::parse("[bindings]\nHint = [{ Key = \"KeyH\" }]\nUndo = [{ Key = \"KeyU\" }]").unwrap();
+        assert_eq!(map.bindings.len(), 1);
+        assert_eq!(map.bindings(Action::Undo), [Binding::Key(KeyCode::KeyU)]);
+    }
+
+    #[test]
+    fn saved_bindings_load_back() {
+        let map = InputMap::default();
+        let loaded = InputMap::parse(&toml::to_string_pretty(&map).unwrap()).unwrap();
+        assert_eq!(loaded.bindings, map.bindings);
+    }
+
+    /// The actions pressed and just pressed during the last update.
+    #[derive(Resource, Default)]
+    struct Seen {
+        pressed: Vec<Action>,
+        just_pressed: Vec<Action>,
+    }
+
+    fn record_actions(actions: Actions, mut seen: ResMut<Seen>) {
+        let all = [Action::Reveal, Action::Flag, Action::Chord, Action::PanUp, Action::PanDown, Action::CursorUp];
+        seen.pressed = all.into_iter().filter(|action| actions.pressed(*action)).collect();
+        seen.just_pressed = all.into_iter().filter(|action| actions.just_pressed(*action)).collect();
+    }
+
+    fn recording_app() -> App {
+        let mut app = test_app();
+        app.init_resource::<Seen>().add_systems(Update, record_actions);
+        app
+    }
+
+    fn seen(app: &App) -> &Seen {
+        app.world().resource::<Seen>()
+    }
+
+    #[test]
+    fn gamepad_buttons_trigger_their_actions() {
+        let mut app = recording_app();
+        let gamepad = connect_gamepad(&mut app);
+
+        set_button(&mut app, gamepad, GamepadButton::South, 1.0);
+        app.update();
+        assert_eq!(seen(&app).just_pressed, vec![Action::Reveal]);
+
+        // Held down, it is no longer just pressed.
+        app.update();
+        assert_eq!(seen(&app).pressed, vec![Action::Reveal]);
+        assert!(seen(&app).just_pressed.is_empty());
+
+        set_button(&mut app, gamepad, GamepadButton::South, 0.0);
+        set_button(&mut app, gamepad, GamepadButton::East, 1.0);
+        app.update();
+        assert_eq!(seen(&app).just_pressed, vec![Action::Flag]);
+        assert_eq!(seen(&app).pressed, vec![Action::Flag]);
+    }
+
+    #[test]
+    fn sticks_only_count_past_the_threshold() {
+        let mut app = recording_app();
+        let gamepad = connect_gamepad(&mut app);
+
+        set_axis(&mut app, gamepad, GamepadAxis::LeftStickY, STICK_THRESHOLD - 0.2);
+        app.update();
+        assert!(seen(&app).pressed.is_empty());
+
+        set_axis(&mut app, gamepad, GamepadAxis::LeftStickY, STICK_THRESHOLD + 0.2);
+        app.update();
+        assert_eq!(seen(&app).pressed, vec![Action::PanUp]);
+        // Sticks are held, never just pressed.
+        assert!(seen(&app).just_pressed.is_empty());
+
+        set_axis(&mut app, gamepad, GamepadAxis::LeftStickY, -(STICK_THRESHOLD + 0.2));
+        app.update();
+        assert_eq!(seen(&app).pressed, vec![Action::PanDown]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::config::{BoardSettings, Difficulty};
+use crate::grid::Grid;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellType {
+    Air(u8),
+    Mine,
+    Wall
+}
+
+/// A hand made board, loaded from a text file. New games use it instead of generating a random board.
+///
+/// Every line of the file is a row of the playable area, top row first:
+/// `*` is a mine, `#` is a wall, and `.` (or a digit, which is ignored) is free space.
+#[derive(Resource, Debug, Clone)]
+pub struct BoardLayout {
+    width: u32,
+    height: u32,
+    /// Row by row, bottom row first, the same as the grid.
+    cells: Vec<CellType>,
+}
+impl BoardLayout {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("could not read '{}': {err}", path.display()))?;
+        Self::parse(&content).map_err(|err| format!("'{}' {err}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = content.lines().map(str::trim_end).filter(|row| !row.is_empty()).collect();
+        let height = rows.len() as u32;
+        let width = rows.first().map_or(0, |row| row.chars().count()) as u32;
+
+        let size = BoardSettings::MIN_SIZE..=BoardSettings::MAX_SIZE;
+        if !size.contains(&width) || !size.contains(&height) {
+            return Err(format!("is {width}x{height}, boards must be between {0}x{0} and {1}x{1}", size.start(), size.end()));
+        }
+
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        for (line, row) in rows.iter().enumerate().rev() {
+            if row.chars().count() as u32 != width {
+                return Err(format!("line {}: expected {width} cells like the first line, found {}", line + 1, row.chars().count()));
+            }
+
+            for (column, char) in row.chars().enumerate() {
+                cells.push(match char {
+                    '*' => CellType::Mine,
+                    '#' => CellType::Wall,
+                    '.' | '0'..='8' => CellType::Air(0),
+                    _ => return Err(format!("line {}, column {}: unexpected '{char}'", line + 1, column + 1)),
+                });
+            }
+        }
+
+        let layout = Self { width, height, cells };
+        let free = layout.count(CellType::Air(0));
+        if layout.count(CellType::Mine) == 0 || free == 0 {
+            return Err("needs at least one mine and one free cell".to_string());
+        }
+        Ok(layout)
+    }
+
+    fn count(&self, cell: CellType) -> u32 {
+        self.cells.iter().filter(|other| **other == cell).count() as u32
+    }
+
+    /// The cell at the given position of the playable area, (0, 0) being the bottom left.
+    pub fn get(&self, x: u32, y: u32) -> CellType {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    /// Settings describing this board, for the menus and statistics.
+    pub fn board_settings(&self) -> BoardSettings {
+        BoardSettings {
+            difficulty: Difficulty::Custom,
+            width: self.width,
+            height: self.height,
+            mines: self.count(CellType::Mine),
+            walls: self.count(CellType::Wall),
+            seed: None,
+        }
+    }
+}
+
+/// Writes a generated grid in the same format BoardLayout reads, with the neighbor counts filled in.
+/// The wall border around the grid is left out.
+pub fn render(grid: &Grid, cells: &[CellType]) -> String {
+    let mut text = String::new();
+    for y in (1..grid.height() - 1).rev() {
+        for x in 1..grid.width() - 1 {
+            text.push(match cells[(y * grid.width() + x) as usize] {
+                CellType::Mine => '*',
+                CellType::Wall => '#',
+                CellType::Air(0) => '.',
+                CellType::Air(n) => char::from(b'0' + n),
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
@@ -1,15 +1,39 @@
 mod camera;
 mod cell;
+mod chunk_mesh;
+mod cli;
+mod config;
+mod cursor;
+mod game;
 mod grid;
 mod env;
+mod highlight;
+mod history;
+mod hud;
+mod input;
+mod layout;
+mod menu;
+mod minimap;
+mod settings;
+mod stats;
+mod theme;
+mod tween;
 
 use bevy::prelude::*;
 
 use crate::grid::Grid;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    #[cfg(debug_assertions)]
-    dotenvy::dotenv()?;
+    let cli = cli::Cli::from_env();
+    let layout = cli.layout().unwrap_or_else(|err| {
+        eprintln!("error: --board {err}");
+        std::process::exit(2);
+    });
+
+    if cli.headless {
+        cli::run_headless(&cli, layout.as_ref());
+        return Ok(());
+    }
 
     let grid = Grid::default();
     // grid.mine_chance = 25.0;
@@ -19,8 +43,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .set(ImagePlugin::default_nearest())    
         )
         .add_plugins(
-            (camera::CameraPlugin, cell::CellPlugin)
+            (config::ConfigPlugin, settings::SettingsPlugin, input::InputPlugin, camera::CameraPlugin, cell::CellPlugin, chunk_mesh::ChunkMeshPlugin, cursor::CursorPlugin, game::GamePlugin)
+        )
+        .add_plugins(
+            (highlight::HighlightPlugin, history::HistoryPlugin, hud::HudPlugin, menu::MenuPlugin, minimap::MinimapPlugin, stats::StatsPlugin, theme::ThemePlugin)
         )
+        .add_plugins(cli::CliPlugin { cli, layout })
         .insert_resource(ClearColor(Color::BLACK))
         .insert_resource(grid)
         .run();
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::config::{Difficulty, UserConfig};
+use crate::game::AppState;
+use crate::menu::{Field, MenuAction, SeedFocus, button, label, screen, stepper, title};
+
+const DIGIT_KEYS: [(KeyCode, u64); 10] = [
+    (KeyCode::Digit0, 0), (KeyCode::Digit1, 1), (KeyCode::Digit2, 2), (KeyCode::Digit3, 3), (KeyCode::Digit4, 4),
+    (KeyCode::Digit5, 5), (KeyCode::Digit6, 6), (KeyCode::Digit7, 7), (KeyCode::Digit8, 8), (KeyCode::Digit9, 9),
+];
+
+/// Marks the text inside of the seed field.
+#[derive(Component)]
+pub(super) struct SeedText;
+
+pub(super) fn spawn_main_menu(
+    mut commands: Commands,
+    config: Res<UserConfig>,
+) {
+    commands.spawn((
+        screen(AppState::MainMenu),
+        children![
+            title("Minesweeper"),
+            (
+                Node { column_gap: Val::Px(8.0), ..default() },
+                children![
+                    button(Difficulty::Beginner.name(), MenuAction::SetDifficulty(Difficulty::Beginner), 170.0),
+                    button(Difficulty::Intermediate.name(), MenuAction::SetDifficulty(Difficulty::Intermediate), 170.0),
+                    button(Difficulty::Expert.name(), MenuAction::SetDifficulty(Difficulty::Expert), 170.0),
+                    button(Difficulty::Custom.name(), MenuAction::SetDifficulty(Difficulty::Custom), 170.0),
+                ],
+            ),
+            stepper(Field::Width, &config),
+            stepper(Field::Height, &config),
+            stepper(Field::Mines, &config),
+            stepper(Field::Walls, &config),
+            (
+                Node { align_items: AlignItems::Center, column_gap: Val::Px(12.0), ..default() },
+                children![
+                    (
+                        label("Seed"),
+                        Node { width: Val::Px(180.0), ..default() },
+                    ),
+                    (
+                        Button,
+                        Node {
+                            width: Val::Px(228.0),
+                            padding: UiRect::all(Val::Px(6.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BackgroundColor::default(),
+                        MenuAction::FocusSeed,
+                        children![(label(""), SeedText)],
+                    ),
+                ],
+            ),
+            button("Play", MenuAction::Play, 240.0),
+            button("Statistics", MenuAction::OpenStatistics, 240.0),
+            button("Settings", MenuAction::OpenSettings, 240.0),
+            button("Quit", MenuAction::Quit, 240.0),
+        ],
+    ));
+}
+
+/// Types digits into the seed while the seed field is focused.
+/// Backspace removes the last digit, an empty seed means a random board. Enter or escape stops typing.
+pub(super) fn seed_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut seed_focus: ResMut<SeedFocus>,
+    mut config: ResMut<UserConfig>,
+) {
+    if !seed_focus.0 { return };
+
+    if keyboard.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Escape]) {
+        seed_focus.0 = false;
+        return;
+    }
+
+    let mut seed = config.board.seed;
+    if keyboard.just_pressed(KeyCode::Backspace) {
+        seed = seed.map(|seed| seed / 10).filter(|seed| *seed != 0);
+    }
+    for (key, digit) in DIGIT_KEYS {
+        if !keyboard.just_pressed(key) { continue };
+        // Digits which would overflow the seed are ignored.
+        if let Some(next) = seed.unwrap_or(0).checked_mul(10).and_then(|seed| seed.checked_add(digit)) {
+            seed = Some(next);
+        }
+    }
+
+    if seed != config.board.seed {
+        config.board.seed = seed;
+    }
+}
+
+pub(super) fn update_seed_text(
+    config: Res<UserConfig>,
+    seed_focus: Res<SeedFocus>,
+    mut texts: Query<&mut Text, With<SeedText>>,
+) {
+    for mut text in texts.iter_mut() {
+        let value = match (config.board.seed, seed_focus.0) {
+            (Some(seed), true) => format!("{seed}_"),
+            (Some(seed), false) => seed.to_string(),
+            (None, true) => "_".to_string(),
+            (None, false) => "Random".to_string(),
+        };
+        if text.0 != value {
+            text.0 = value;
+        }
+    }
+}
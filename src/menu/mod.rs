@@ -0,0 +1,311 @@
+mod main_menu;
+mod settings;
+mod statistics;
+
+use bevy::prelude::*;
+
+use crate::config::{Difficulty, UserConfig};
+use crate::game::{AppState, NewGame};
+use crate::theme::{self, Palette};
+
+pub struct MenuPlugin;
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SeedFocus>()
+            .add_systems(OnEnter(AppState::MainMenu), main_menu::spawn_main_menu)
+            .add_systems(OnEnter(AppState::Settings), settings::spawn_settings)
+            .add_systems(OnEnter(AppState::Statistics), statistics::spawn_statistics)
+            .add_systems(OnExit(AppState::MainMenu), |mut focus: ResMut<SeedFocus>| focus.0 = false)
+            .add_systems(Update, (
+                menu_actions,
+                button_colors,
+                update_field_text.run_if(resource_changed::<UserConfig>),
+                main_menu::seed_input,
+                main_menu::update_seed_text,
+                statistics::refresh_statistics,
+            ).run_if(not(in_state(AppState::InGame))))
+            .add_systems(Update, back_to_main_menu.run_if(in_state(AppState::Settings).or(in_state(AppState::Statistics))));
+    }
+}
+
+const TITLE_FONT_SIZE: f32 = 48.0;
+const MENU_FONT_SIZE: f32 = 24.0;
+const MENU_BACKGROUND: Color = Color::srgb(0.08, 0.08, 0.1);
+const BUTTON_IDLE: Color = Color::srgb(0.25, 0.25, 0.28);
+const BUTTON_HOVERED: Color = Color::srgb(0.35, 0.35, 0.38);
+const BUTTON_PRESSED: Color = Color::srgb(0.18, 0.18, 0.2);
+const BUTTON_SELECTED: Color = Color::srgb(0.2, 0.45, 0.3);
+
+/// What happens when a menu button is pressed.
+#[derive(Component, Clone, Copy, PartialEq)]
+enum MenuAction {
+    Play,
+    OpenSettings,
+    OpenStatistics,
+    BackToMenu,
+    Quit,
+    SetDifficulty(Difficulty),
+    /// Changes a field by the given number of steps.
+    Step(Field, i32),
+    FocusSeed,
+    /// Plays the selected board again from the given seed.
+    Replay(u64),
+}
+
+/// A value in the user config which can be edited from the menus.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Width,
+    Height,
+    Mines,
+    Walls,
+    CameraSpeed,
+    CellScale,
+    MaxZoomOut,
+    EdgePan,
+    QuestionMarks,
+    Practice,
+    Lives,
+    Theme,
+    Palette
+}
+impl Field {
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Width => "Width",
+            Field::Height => "Height",
+            Field::Mines => "Mines",
+            Field::Walls => "Walls",
+            Field::CameraSpeed => "Camera speed",
+            Field::CellScale => "Cell scale",
+            Field::MaxZoomOut => "Max zoom out",
+            Field::EdgePan => "Edge panning",
+            Field::QuestionMarks => "Question marks",
+            Field::Practice => "Practice mode",
+            Field::Lives => "Lives",
+            Field::Theme => "Theme",
+            Field::Palette => "Colours",
+        }
+    }
+
+    fn display(&self, config: &UserConfig) -> String {
+        match self {
+            Field::Width => config.board.width.to_string(),
+            Field::Height => config.board.height.to_string(),
+            Field::Mines => config.board.mines.to_string(),
+            Field::Walls => config.board.walls.to_string(),
+            Field::CameraSpeed => format!("{:.1}", config.camera_speed),
+            Field::CellScale => format!("{:.2}", config.cell_scale),
+            Field::MaxZoomOut => format!("{:.1}x", config.max_zoom_out),
+            Field::EdgePan => if config.edge_pan { "On" } else { "Off" }.to_string(),
+            Field::QuestionMarks => if config.question_marks { "On" } else { "Off" }.to_string(),
+            Field::Practice => if config.practice { "On" } else { "Off" }.to_string(),
+            Field::Lives => config.lives.to_string(),
+            Field::Theme => theme::find(&config.theme).name().to_string(),
+            Field::Palette => config.palette.name().to_string(),
+        }
+    }
+
+    /// Board fields move by whole cells, settings by fixed increments.
+    /// Editing any board field turns the board into a custom one.
+    fn step(&self, config: &mut UserConfig, steps: i32) {
+        let board = &mut config.board;
+        match self {
+            Field::Width => board.width = board.width.saturating_add_signed(steps),
+            Field::Height => board.height = board.height.saturating_add_signed(steps),
+            Field::Mines => board.mines = board.mines.saturating_add_signed(steps),
+            Field::Walls => board.walls = board.walls.saturating_add_signed(steps),
+            Field::CameraSpeed => config.camera_speed += 0.5 * steps as f32,
+            Field::CellScale => config.cell_scale += 0.25 * steps as f32,
+            Field::MaxZoomOut => config.max_zoom_out += 0.5 * steps as f32,
+            // Toggles flip with either button.
+            Field::EdgePan => config.edge_pan = !config.edge_pan,
+            Field::QuestionMarks => config.question_marks = !config.question_marks,
+            Field::Practice => config.practice = !config.practice,
+            Field::Lives => config.lives = config.lives.saturating_add_signed(steps),
+            // Cycles through the available themes, wrapping around at either end.
+            Field::Theme => {
+                let themes = theme::available();
+                let current = themes.iter().position(|theme| theme.id() == config.theme).unwrap_or(0);
+                let next = (current as i32 + steps).rem_euclid(themes.len() as i32);
+                config.theme = themes[next as usize].id().to_string();
+            },
+            Field::Palette => {
+                let current = Palette::ALL.iter().position(|palette| *palette == config.palette).unwrap_or(0);
+                let next = (current as i32 + steps).rem_euclid(Palette::ALL.len() as i32);
+                config.palette = Palette::ALL[next as usize];
+            },
+        }
+
+        if matches!(self, Field::Width | Field::Height | Field::Mines | Field::Walls) {
+            config.board.difficulty = Difficulty::Custom;
+        }
+        config.clamp();
+    }
+}
+
+/// Marks the text displaying the value of a field.
+#[derive(Component)]
+struct FieldText(Field);
+
+/// Whether typed digits go into the seed field.
+#[derive(Resource, Default)]
+struct SeedFocus(bool);
+
+/// Root node of a menu screen, filling the window and centering its children in a column.
+fn screen(state: AppState) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+        BackgroundColor(MENU_BACKGROUND),
+        DespawnOnExit(state),
+    )
+}
+
+fn title(text: &str) -> impl Bundle + use<> {
+    (
+        Text::new(text),
+        TextFont::from_font_size(TITLE_FONT_SIZE),
+        TextColor(Color::WHITE),
+    )
+}
+
+fn label(text: &str) -> impl Bundle + use<> {
+    (
+        Text::new(text),
+        TextFont::from_font_size(MENU_FONT_SIZE),
+        TextColor(Color::WHITE),
+    )
+}
+
+fn button(text: &str, action: MenuAction, width: f32) -> impl Bundle + use<> {
+    (
+        Button,
+        Node {
+            width: Val::Px(width),
+            padding: UiRect::all(Val::Px(6.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_IDLE),
+        action,
+        children![label(text)],
+    )
+}
+
+/// A row of `label  [-] value [+]` editing the given field.
+fn stepper(field: Field, config: &UserConfig) -> impl Bundle + use<> {
+    (
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(12.0),
+            ..default()
+        },
+        children![
+            (
+                label(field.label()),
+                Node { width: Val::Px(180.0), ..default() },
+            ),
+            button("-", MenuAction::Step(field, -1), 40.0),
+            (
+                label(&field.display(config)),
+                Node { width: Val::Px(160.0), justify_content: JustifyContent::Center, ..default() },
+                FieldText(field),
+            ),
+            button("+", MenuAction::Step(field, 1), 40.0),
+        ],
+    )
+}
+
+fn menu_actions(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<UserConfig>,
+    mut seed_focus: ResMut<SeedFocus>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut new_game: MessageWriter<NewGame>,
+    mut exit: MessageWriter<AppExit>,
+    buttons: Query<(&Interaction, &MenuAction), Changed<Interaction>>,
+) {
+    for (interaction, action) in buttons.iter() {
+        if *interaction != Interaction::Pressed { continue };
+
+        // Clicking anywhere but the seed field stops typing into it.
+        seed_focus.0 = *action == MenuAction::FocusSeed;
+
+        match *action {
+            MenuAction::Play => {
+                new_game.write(NewGame { seed: config.board.seed });
+                next_state.set(AppState::InGame);
+            },
+            MenuAction::OpenSettings => next_state.set(AppState::Settings),
+            MenuAction::OpenStatistics => next_state.set(AppState::Statistics),
+            MenuAction::BackToMenu => next_state.set(AppState::MainMenu),
+            MenuAction::Quit => { exit.write(AppExit::Success); },
+            MenuAction::SetDifficulty(difficulty) => config.board.set_difficulty(difficulty),
+            MenuAction::Step(field, steps) => {
+                // Shift moves in larger increments.
+                let multiplier = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) { 10 } else { 1 };
+                field.step(&mut config, steps * multiplier);
+            },
+            MenuAction::FocusSeed => {},
+            MenuAction::Replay(seed) => {
+                new_game.write(NewGame::retry(seed));
+                next_state.set(AppState::InGame);
+            },
+        }
+    }
+}
+
+/// Colours every menu button by its interaction.
+/// The selected difficulty and the focused seed field stay highlighted.
+fn button_colors(
+    config: Res<UserConfig>,
+    seed_focus: Res<SeedFocus>,
+    mut buttons: Query<(&Interaction, &MenuAction, &mut BackgroundColor)>,
+) {
+    for (interaction, action, mut background) in buttons.iter_mut() {
+        let selected = match action {
+            MenuAction::SetDifficulty(difficulty) => *difficulty == config.board.difficulty,
+            MenuAction::FocusSeed => seed_focus.0,
+            _ => false
+        };
+
+        let color = match interaction {
+            Interaction::Pressed => BUTTON_PRESSED,
+            Interaction::Hovered => BUTTON_HOVERED,
+            Interaction::None if selected => BUTTON_SELECTED,
+            Interaction::None => BUTTON_IDLE,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+fn update_field_text(
+    config: Res<UserConfig>,
+    mut texts: Query<(&mut Text, &FieldText)>,
+) {
+    for (mut text, FieldText(field)) in texts.iter_mut() {
+        text.0 = field.display(&config);
+    }
+}
+
+/// Escape goes back to the main menu from any of its sub menus.
+fn back_to_main_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::MainMenu);
+    }
+}
@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+
+use crate::config::UserConfig;
+use crate::game::AppState;
+use crate::menu::{Field, MenuAction, button, screen, stepper, title};
+
+pub(super) fn spawn_settings(
+    mut commands: Commands,
+    config: Res<UserConfig>,
+) {
+    commands.spawn((
+        screen(AppState::Settings),
+        children![
+            title("Settings"),
+            stepper(Field::CameraSpeed, &config),
+            stepper(Field::CellScale, &config),
+            stepper(Field::MaxZoomOut, &config),
+            stepper(Field::EdgePan, &config),
+            stepper(Field::QuestionMarks, &config),
+            stepper(Field::Practice, &config),
+            stepper(Field::Lives, &config),
+            stepper(Field::Theme, &config),
+            stepper(Field::Palette, &config),
+            button("Back", MenuAction::BackToMenu, 240.0),
+        ],
+    ));
+}
+
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::config::{Difficulty, UserConfig};
+use crate::game::AppState;
+use crate::menu::{MenuAction, button, label, screen, title};
+use crate::stats::Statistics;
+
+/// Marks the node holding the statistics of the selected board. Rebuilt whenever the selection changes.
+#[derive(Component)]
+pub(super) struct StatsContent;
+
+pub(super) fn spawn_statistics(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        screen(AppState::Statistics),
+        children![
+            title("Statistics"),
+            (
+                Node { column_gap: Val::Px(8.0), ..default() },
+                children![
+                    button(Difficulty::Beginner.name(), MenuAction::SetDifficulty(Difficulty::Beginner), 170.0),
+                    button(Difficulty::Intermediate.name(), MenuAction::SetDifficulty(Difficulty::Intermediate), 170.0),
+                    button(Difficulty::Expert.name(), MenuAction::SetDifficulty(Difficulty::Expert), 170.0),
+                    button(Difficulty::Custom.name(), MenuAction::SetDifficulty(Difficulty::Custom), 170.0),
+                ],
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                StatsContent,
+            ),
+            button("Back", MenuAction::BackToMenu, 240.0),
+        ],
+    ));
+}
+
+fn seconds(time: Option<f32>) -> String {
+    time.map_or("-".to_string(), |time| format!("{time:.2}s"))
+}
+
+/// Fills in the statistics and leaderboard of the board currently selected in the config.
+/// Each leaderboard entry can be replayed from its seed.
+pub(super) fn refresh_statistics(
+    mut commands: Commands,
+    config: Res<UserConfig>,
+    stats: Res<Statistics>,
+    content: Query<(Entity, Ref<StatsContent>)>,
+) {
+    let board = &config.board;
+    let board_stats = stats.get(board).cloned().unwrap_or_default();
+
+    for (entity, marker) in content.iter() {
+        // Rebuild when the screen opens, or when another board is selected.
+        if !marker.is_added() && !config.is_changed() && !stats.is_changed() { continue };
+
+        let mut content = commands.entity(entity);
+        content.despawn_children();
+
+        content.with_children(|parent| {
+            parent.spawn(label(&format!("{} ({}x{}, {} mines, {} walls)", board.difficulty.name(), board.width, board.height, board.mines, board.walls)));
+            parent.spawn(label(&format!("Played: {}   Won: {} ({:.0}%)", board_stats.played, board_stats.won, board_stats.win_rate())));
+            parent.spawn(label(&format!("Best time: {}   Best 3BV/s: {}", seconds(board_stats.best_time), board_stats.best_bbbv_per_second.map_or("-".to_string(), |bbbv| format!("{bbbv:.2}")))));
+            parent.spawn(label(&format!("Streak: {}   Best streak: {}", board_stats.current_streak, board_stats.best_streak)));
+
+            if board_stats.leaderboard.is_empty() {
+                parent.spawn(label("No wins yet."));
+                return;
+            }
+
+            parent.spawn(label(&format!("{:>2}  {:>8}  {:>4}  {:>6}  {:>20}  {:>10}", "#", "Time", "3BV", "3BV/s", "Seed", "Date")));
+            for (rank, score) in board_stats.leaderboard.iter().enumerate() {
+                parent.spawn((
+                    Node { align_items: AlignItems::Center, column_gap: Val::Px(12.0), ..default() },
+                    children![
+                        label(&format!(
+                            "{:>2}  {:>8}  {:>4}  {:>6.2}  {:>20}  {:>10}",
+                            rank + 1, seconds(Some(score.time)), score.bbbv, score.bbbv_per_second(), score.seed, score.date
+                        )),
+                        button("Replay", MenuAction::Replay(score.seed), 100.0),
+                    ],
+                ));
+            }
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    ui::RelativeCursorPosition,
+};
+
+use crate::camera::CenterCamera;
+use crate::cell::{BoardChanged, Cover};
+use crate::game::AppState;
+use crate::grid::Grid;
+
+pub struct MinimapPlugin;
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), spawn_minimap)
+            .add_systems(Update, (
+                draw_minimap,
+                update_viewport,
+                minimap_click,
+            ).run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Length of the longer side of the minimap, in pixels.
+const MINIMAP_SIZE: f32 = 200.0;
+const MINIMAP_MARGIN: f32 = 12.0;
+
+const HIDDEN_COLOR: Color = Color::srgb(0.35, 0.35, 0.38);
+const OPEN_COLOR: Color = Color::srgb(0.1, 0.1, 0.12);
+const NUMBER_COLOR: Color = Color::srgb(0.2, 0.2, 0.24);
+const WALL_COLOR: Color = Color::srgb(0.34, 0.45, 0.47);
+const FLAG_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+
+/// Marks the image node showing the board, one pixel per cell.
+#[derive(Component)]
+struct Minimap;
+
+/// Marks the rectangle showing what the camera currently sees.
+#[derive(Component)]
+struct MinimapViewport;
+
+fn spawn_minimap(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(MINIMAP_MARGIN),
+            bottom: Val::Px(MINIMAP_MARGIN),
+            border: UiRect::all(Val::Px(2.0)),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BorderColor::all(Color::srgb(0.6, 0.6, 0.6)),
+        // Replaced with an image of the right size once the board exists.
+        ImageNode::default(),
+        Interaction::default(),
+        RelativeCursorPosition::default(),
+        Minimap,
+        DespawnOnExit(AppState::InGame),
+        children![(
+            Node {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BorderColor::all(Color::WHITE),
+            MinimapViewport,
+        )],
+    ));
+}
+
+/// Redraws the minimap from the grid whenever a cell is revealed or flagged.
+/// A new board gets a new image, sized one pixel per cell.
+fn draw_minimap(
+    grid: Res<Grid>,
+    mut images: ResMut<Assets<Image>>,
+    mut board_changed: MessageReader<BoardChanged>,
+    mut minimap: Query<(Ref<Minimap>, &mut ImageNode, &mut Node)>,
+) {
+    let Ok((marker, mut image_node, mut node)) = minimap.single_mut() else { return };
+
+    let resized = board_changed.read().count() > 0 || marker.is_added();
+    if !resized && !grid.is_changed() { return };
+
+    if resized {
+        let size = Extent3d { width: grid.width(), height: grid.height(), depth_or_array_layers: 1 };
+        let image = Image::new_fill(size, TextureDimension::D2, &[0, 0, 0, 255], TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+        image_node.image = images.add(image);
+
+        // Keep the aspect ratio of the board.
+        let longest = grid.width().max(grid.height()) as f32;
+        node.width = Val::Px(MINIMAP_SIZE * grid.width() as f32 / longest);
+        node.height = Val::Px(MINIMAP_SIZE * grid.height() as f32 / longest);
+    }
+
+    let Some(image) = images.get_mut(&image_node.image) else { return };
+    for (x, y, cell) in grid.iter() {
+        let color = match cell.cover() {
+            _ if cell.is_wall() => WALL_COLOR,
+            Cover::Flagged => FLAG_COLOR,
+            Cover::Revealed if !cell.is_air() => HIDDEN_COLOR,
+            Cover::Revealed if cell.neighbor_mines() == 0 => OPEN_COLOR,
+            Cover::Revealed => NUMBER_COLOR,
+            Cover::Hidden | Cover::Questioned => HIDDEN_COLOR,
+        };
+
+        // Images go top down, the board goes bottom up.
+        let _ = image.set_color_at(x as u32, grid.height() - 1 - y as u32, color);
+    }
+}
+
+/// Converts a world position into a fraction of the board, (0, 0) being the top left and (1, 1) the bottom right.
+fn world_to_fraction(grid: &Grid, world: Vec2) -> Vec2 {
+    let cell_size = grid.cell_size() as f32;
+    let board_size = Vec2::new(grid.width() as f32, grid.height() as f32) * cell_size;
+
+    // Cell (0, 0) is centred on the origin, so the board starts half a cell before it.
+    let fraction = (world + cell_size / 2.0) / board_size;
+    Vec2::new(fraction.x, 1.0 - fraction.y)
+}
+
+fn fraction_to_world(grid: &Grid, fraction: Vec2) -> Vec2 {
+    let cell_size = grid.cell_size() as f32;
+    let board_size = Vec2::new(grid.width() as f32, grid.height() as f32) * cell_size;
+
+    Vec2::new(fraction.x, 1.0 - fraction.y) * board_size - cell_size / 2.0
+}
+
+/// Moves the viewport rectangle over the part of the board the camera can see.
+fn update_viewport(
+    grid: Res<Grid>,
+    camera: Query<(&Transform, &Projection), With<Camera2d>>,
+    mut viewport: Query<&mut Node, With<MinimapViewport>>,
+) {
+    let Ok((transform, projection)) = camera.single() else { return };
+    let Projection::Orthographic(ortho) = projection else { return };
+    let Ok(mut node) = viewport.single_mut() else { return };
+
+    let centre = transform.translation.truncate();
+    let top_left = world_to_fraction(&grid, centre + Vec2::new(ortho.area.min.x, ortho.area.max.y));
+    let bottom_right = world_to_fraction(&grid, centre + Vec2::new(ortho.area.max.x, ortho.area.min.y));
+    let size = bottom_right - top_left;
+
+    node.left = Val::Percent(top_left.x * 100.0);
+    node.top = Val::Percent(top_left.y * 100.0);
+    node.width = Val::Percent(size.x * 100.0);
+    node.height = Val::Percent(size.y * 100.0);
+}
+
+/// Clicking (or dragging) on the minimap centres the camera on that point of the board.
+fn minimap_click(
+    grid: Res<Grid>,
+    mut writer: MessageWriter<CenterCamera>,
+    minimap: Query<(&Interaction, &RelativeCursorPosition), With<Minimap>>,
+) {
+    let Ok((interaction, cursor)) = minimap.single() else { return };
+    if *interaction != Interaction::Pressed { return };
+    let Some(normalized) = cursor.normalized else { return };
+
+    // Normalized is relative to the centre of the node.
+    writer.write(CenterCamera { position: fraction_to_world(&grid, normalized + 0.5) });
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::config::UserConfig;
+use crate::env;
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Settings>()
+            .add_message::<SettingsChanged>()
+            .add_systems(Startup, report_config_errors)
+            .add_systems(Update, apply_user_config.run_if(resource_changed::<UserConfig>));
+    }
+}
+
+/// The values the game runs with. Starts out from the embedded defaults, with the user's config layered on top.
+/// Systems should read these every time they need them, they can change at any point.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Proportions of each chunk in cells. Only picked up by new boards.
+    pub chunk_width: u32,
+    pub chunk_height: u32,
+    /// Size of a cell before scaling, in pixels.
+    pub cell_size: u32,
+    /// Scale applied to every cell asset.
+    pub cell_scale: f32,
+    /// Cells per second.
+    pub camera_speed: f32,
+}
+impl Settings {
+    /// Size of a cell once scaled, in pixels.
+    pub fn scaled_cell_size(&self) -> u32 {
+        (self.cell_size as f32 * self.cell_scale).floor() as u32
+    }
+}
+impl Default for Settings {
+    fn default() -> Self {
+        let defaults = env::defaults();
+        Self {
+            chunk_width: defaults.chunk_width,
+            chunk_height: defaults.chunk_height,
+            cell_size: defaults.cell_size,
+            cell_scale: defaults.cell_scale,
+            camera_speed: defaults.camera_speed,
+        }
+    }
+}
+
+/// Sent after the settings changed, so anything derived from them (the grid, camera, ...) can catch up.
+#[derive(Message)]
+pub struct SettingsChanged;
+
+/// Lists every problem found in the defaults at once, rather than stopping at the first one.
+fn report_config_errors() {
+    let errors = env::errors();
+    if errors.is_empty() { return };
+
+    let report: String = errors.iter().map(|err| format!("\n  - {err}")).collect();
+    error!("Found {} problem(s) in the default settings, the affected values use their built in defaults:{report}", errors.len());
+}
+
+/// Copies the user's choices from the menus over the settings.
+fn apply_user_config(
+    config: Res<UserConfig>,
+    mut settings: ResMut<Settings>,
+    mut writer: MessageWriter<SettingsChanged>,
+) {
+    let updated = Settings {
+        cell_scale: config.cell_scale,
+        camera_speed: config.camera_speed,
+        ..settings.clone()
+    };
+
+    if *settings != updated {
+        *settings = updated;
+        writer.write(SettingsChanged);
+    }
+}
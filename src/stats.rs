@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cell::CellState;
+use crate::cli::CurrentBoard;
+use crate::config::{BoardSettings, Difficulty, UserConfig, user_dir};
+use crate::game::{GameState, GameTimer};
+use crate::grid::Grid;
+
+const STATS_FILE: &str = "statistics.toml";
+const LEADERBOARD_SIZE: usize = 10;
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(Statistics::load())
+            .add_systems(OnEnter(GameState::Won), record_win.run_if(classic_rules))
+            .add_systems(OnEnter(GameState::Lost), record_loss.run_if(classic_rules));
+    }
+}
+
+/// A single won game on the leaderboard.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Score {
+    /// Seconds taken to clear the board.
+    pub time: f32,
+    /// The 3BV of the board, the minimum number of clicks needed to clear it.
+    pub bbbv: u32,
+    /// Seed of the board, so it can be replayed.
+    pub seed: u64,
+    /// Date the game was won on, as YYYY-MM-DD.
+    pub date: String
+}
+impl Score {
+    pub fn bbbv_per_second(&self) -> f32 {
+        self.bbbv as f32 / self.time.max(f32::EPSILON)
+    }
+}
+
+/// Statistics for every game played on one kind of board.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct BoardStats {
+    pub played: u32,
+    pub won: u32,
+    pub best_time: Option<f32>,
+    pub best_bbbv_per_second: Option<f32>,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// The fastest wins, best first.
+    pub leaderboard: Vec<Score>
+}
+impl BoardStats {
+    pub fn win_rate(&self) -> f32 {
+        if self.played == 0 { return 0.0 };
+        self.won as f32 / self.played as f32 * 100.0
+    }
+
+    fn record_win(&mut self, score: Score) {
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        self.best_time = Some(self.best_time.map_or(score.time, |best| best.min(score.time)));
+        self.best_bbbv_per_second = Some(self.best_bbbv_per_second.map_or(score.bbbv_per_second(), |best| best.max(score.bbbv_per_second())));
+
+        self.leaderboard.push(score);
+        self.leaderboard.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+
+    fn record_loss(&mut self) {
+        self.played += 1;
+        self.current_streak = 0;
+    }
+}
+
+/// Statistics of every board the player has played, stored in the user's data directory.
+/// Preset difficulties are tracked by name, custom boards separately by their dimensions.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Statistics {
+    pub boards: BTreeMap<String, BoardStats>
+}
+impl Statistics {
+    fn path() -> PathBuf {
+        user_dir().join(STATS_FILE)
+    }
+
+    /// The key statistics of the given board are stored under.
+    pub fn key(board: &BoardSettings) -> String {
+        match board.difficulty {
+            Difficulty::Custom => format!("custom-{}x{}-{}m-{}w", board.width, board.height, board.mines, board.walls),
+            difficulty => difficulty.name().to_lowercase()
+        }
+    }
+
+    pub fn get(&self, board: &BoardSettings) -> Option<&BoardStats> {
+        self.boards.get(&Self::key(board))
+    }
+
+    /// Loads the statistics file, starting fresh when it is missing or unreadable.
+    pub fn load() -> Self {
+        let Ok(content) = fs::read_to_string(Self::path()) else { return Self::default() };
+
+        toml::from_str(&content).unwrap_or_else(|err| {
+            warn!("Ignoring malformed statistics file '{}': {}", Self::path().display(), err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let content = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::create_dir_all(user_dir())?;
+        fs::write(Self::path(), content)
+    }
+
+    fn board_mut(&mut self, board: &BoardSettings) -> &mut BoardStats {
+        self.boards.entry(Self::key(board)).or_default()
+    }
+
+    fn save_or_log(&self) {
+        if let Err(err) = self.save() {
+            error!("Failed to save statistics file '{}': {}", Self::path().display(), err);
+        }
+    }
+}
+
+/// Bechtel's Board Benchmark Value: the minimum number of clicks needed to clear the board.
+/// Every opening (connected area of 0 cells) takes one click, as does every numbered cell not bordering an opening.
+pub fn three_bv(grid: &Grid) -> u32 {
+    let air_at = |x: i32, y: i32| grid.get(x, y).filter(CellState::is_air);
+
+    let mut visited = vec![false; (grid.width() * grid.height()) as usize];
+    let idx = |x: i32, y: i32| (y as usize * grid.width() as usize) + x as usize;
+    let mut clicks = 0;
+
+    // Flood each opening, marking it and its numbered border as visited.
+    for x in 0..grid.width() as i32 {
+        for y in 0..grid.height() as i32 {
+            let Some(air) = air_at(x, y) else { continue };
+            if air.neighbor_mines() != 0 || visited[idx(x, y)] { continue };
+
+            clicks += 1;
+            visited[idx(x, y)] = true;
+            let mut queue = vec![(x, y)];
+            while let Some((x, y)) = queue.pop() {
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        let (nx, ny) = (x + dx, y + dy);
+                        let Some(neighbor) = air_at(nx, ny) else { continue };
+                        if visited[idx(nx, ny)] { continue };
+
+                        visited[idx(nx, ny)] = true;
+                        if neighbor.neighbor_mines() == 0 {
+                            queue.push((nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Every numbered cell left over needs its own click.
+    for x in 0..grid.width() as i32 {
+        for y in 0..grid.height() as i32 {
+            if air_at(x, y).is_some() && !visited[idx(x, y)] {
+                clicks += 1;
+            }
+        }
+    }
+
+    clicks
+}
+
+/// Today's date as YYYY-MM-DD (UTC).
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0) as i64;
+
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Only games played by the classic rules count. Practice games can take back a loss, and extra lives make boards easier.
+fn classic_rules(config: Res<UserConfig>) -> bool {
+    !config.practice && config.lives == 1
+}
+
+fn record_win(
+    grid: Res<Grid>,
+    board: CurrentBoard,
+    timer: Res<GameTimer>,
+    mut stats: ResMut<Statistics>,
+) {
+    let score = Score {
+        time: timer.0.elapsed_secs(),
+        bbbv: three_bv(&grid),
+        seed: grid.seed(),
+        date: today(),
+    };
+
+    stats.board_mut(&board.settings()).record_win(score);
+    stats.save_or_log();
+}
+
+fn record_loss(
+    board: CurrentBoard,
+    mut stats: ResMut<Statistics>,
+) {
+    stats.board_mut(&board.settings()).record_loss();
+    stats.save_or_log();
+}
@@ -0,0 +1,347 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::OnceLock};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::UserConfig;
+
+/// Theme every other theme falls back on. Embedded, so there is always one to use.
+pub const DEFAULT_THEME: &str = "classic";
+const DEFAULT_MANIFEST: &str = include_str!("../assets/themes/classic.toml");
+/// Directory of the theme manifests, inside of the assets directory.
+const THEME_DIR: &str = "themes";
+
+pub struct ThemePlugin;
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, load_theme)
+            .add_systems(Update, load_theme.run_if(resource_changed::<UserConfig>));
+    }
+}
+
+/// Every way a cell can be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellTile {
+    /// Border of a cell which has not been revealed yet.
+    Hidden,
+    /// Border of a revealed cell with mines around it.
+    Revealed,
+    /// Border of a hidden cell held down by the mouse.
+    Pressed,
+    /// Number of mines around a revealed cell, from 1 to 8.
+    Number(u8),
+    Flag,
+    /// Marks a cell the player is unsure about.
+    Question,
+    Mine,
+    /// The mine which ended the game.
+    Exploded,
+    Wall,
+    /// A flag placed on a cell which was not a mine.
+    WrongFlag,
+}
+impl CellTile {
+    pub const ALL: [CellTile; 17] = [
+        CellTile::Hidden, CellTile::Revealed, CellTile::Pressed,
+        CellTile::Number(1), CellTile::Number(2), CellTile::Number(3), CellTile::Number(4),
+        CellTile::Number(5), CellTile::Number(6), CellTile::Number(7), CellTile::Number(8),
+        CellTile::Flag, CellTile::Question, CellTile::Mine, CellTile::Exploded, CellTile::Wall, CellTile::WrongFlag,
+    ];
+}
+
+/// Replaces some of the colours (or tiles) of a theme, for players who have trouble telling them apart.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    /// The theme as it is.
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+impl Palette {
+    pub const ALL: [Palette; 5] = [Palette::Standard, Palette::Deuteranopia, Palette::Protanopia, Palette::Tritanopia, Palette::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+            Palette::HighContrast => "High contrast",
+        }
+    }
+}
+
+/// A tile of the atlas, as written in the manifest.
+#[derive(Deserialize)]
+struct RawTile {
+    index: usize,
+    tint: Option<String>,
+}
+
+/// A tile of the atlas and the colour it is tinted with.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "RawTile")]
+pub struct Tile {
+    index: usize,
+    tint: Color,
+}
+impl TryFrom<RawTile> for Tile {
+    type Error = String;
+
+    fn try_from(raw: RawTile) -> Result<Self, String> {
+        let tint = match raw.tint {
+            Some(hex) => Srgba::hex(&hex).map_err(|err| format!("invalid tint \"{hex}\": {err}"))?.into(),
+            None => Color::WHITE,
+        };
+        Ok(Self { index: raw.index, tint })
+    }
+}
+
+/// Describes a theme: which atlas it draws from, and which tile of it every cell state uses.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ThemeManifest {
+    /// File name of the manifest, without the extension. This is what the user config refers to.
+    #[serde(skip)]
+    id: String,
+    name: String,
+    /// Path of the atlas image, relative to the assets directory.
+    atlas: String,
+    tile_size: u32,
+    columns: u32,
+    rows: u32,
+    hidden: Tile,
+    revealed: Tile,
+    /// Drawn in place of a held down hidden cell. Themes without one show the hidden tile.
+    #[serde(default)]
+    pressed: Option<Tile>,
+    numbers: [Tile; 8],
+    flag: Tile,
+    /// Drawn on question-marked cells. Required, since nothing else tells them apart from flags or hidden cells.
+    question: Tile,
+    mine: Tile,
+    exploded: Tile,
+    wall: Tile,
+    wrong_flag: Tile,
+    /// Palettes the theme provides. Picking one it does not provide draws the theme as it is.
+    #[serde(default)]
+    palettes: HashMap<Palette, PaletteTiles>,
+}
+impl ThemeManifest {
+    pub fn parse(id: &str, content: &str) -> Result<Self, String> {
+        let mut manifest: Self = toml::from_str(content).map_err(|err| err.message().to_string())?;
+        manifest.id = id.to_string();
+
+        let tiles = (manifest.columns * manifest.rows) as usize;
+        for palette in Palette::ALL {
+            for state in CellTile::ALL {
+                let index = manifest.tile(state, palette).index;
+                if index >= tiles {
+                    return Err(format!("{state:?} uses tile {index} in the {} palette, but the atlas only has {tiles}", palette.name()));
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The tile of the given state, as the palette draws it.
+    /// States the theme leaves out are drawn as the state they stand in for.
+    pub fn tile(&self, state: CellTile, palette: Palette) -> Tile {
+        let tile = self.palettes.get(&palette).and_then(|tiles| tiles.tile(state)).or_else(|| self.base_tile(state));
+        match (tile, state) {
+            (Some(tile), _) => tile,
+            (None, CellTile::Pressed) => self.tile(CellTile::Hidden, palette),
+            (None, state) => panic!("The theme has no tile for {state:?}."),
+        }
+    }
+
+    fn base_tile(&self, state: CellTile) -> Option<Tile> {
+        match state {
+            CellTile::Hidden => Some(self.hidden),
+            CellTile::Revealed => Some(self.revealed),
+            CellTile::Pressed => self.pressed,
+            CellTile::Number(n @ 1..=8) => Some(self.numbers[n as usize - 1]),
+            CellTile::Number(n) => panic!("No tile for a cell with {n} neighboring mines."),
+            CellTile::Flag => Some(self.flag),
+            CellTile::Question => Some(self.question),
+            CellTile::Mine => Some(self.mine),
+            CellTile::Exploded => Some(self.exploded),
+            CellTile::Wall => Some(self.wall),
+            CellTile::WrongFlag => Some(self.wrong_flag),
+        }
+    }
+}
+
+/// The tiles a palette replaces, anything left out is drawn as the theme draws it.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+struct PaletteTiles {
+    hidden: Option<Tile>,
+    revealed: Option<Tile>,
+    pressed: Option<Tile>,
+    numbers: Option<[Tile; 8]>,
+    flag: Option<Tile>,
+    question: Option<Tile>,
+    mine: Option<Tile>,
+    exploded: Option<Tile>,
+    wall: Option<Tile>,
+    wrong_flag: Option<Tile>,
+}
+impl PaletteTiles {
+    fn tile(&self, state: CellTile) -> Option<Tile> {
+        match state {
+            CellTile::Hidden => self.hidden,
+            CellTile::Revealed => self.revealed,
+            CellTile::Pressed => self.pressed,
+            CellTile::Number(n @ 1..=8) => self.numbers.map(|numbers| numbers[n as usize - 1]),
+            CellTile::Number(n) => panic!("No tile for a cell with {n} neighboring mines."),
+            CellTile::Flag => self.flag,
+            CellTile::Question => self.question,
+            CellTile::Mine => self.mine,
+            CellTile::Exploded => self.exploded,
+            CellTile::Wall => self.wall,
+            CellTile::WrongFlag => self.wrong_flag,
+        }
+    }
+}
+
+/// Every theme that can be picked: the embedded default first, then the manifests found in the assets directory.
+/// Manifests which fail to load are left out with a warning.
+pub fn available() -> &'static [ThemeManifest] {
+    static THEMES: OnceLock<Vec<ThemeManifest>> = OnceLock::new();
+    THEMES.get_or_init(|| {
+        let mut themes = vec![ThemeManifest::parse(DEFAULT_THEME, DEFAULT_MANIFEST).expect("The default theme is invalid.")];
+
+        let dir = FileAssetReader::get_base_path().join("assets").join(THEME_DIR);
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir).into_iter().flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            if id == DEFAULT_THEME { continue };
+
+            let manifest = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| ThemeManifest::parse(id, &content));
+            match manifest {
+                Ok(manifest) => themes.push(manifest),
+                Err(err) => warn!("Ignoring theme '{}': {}", path.display(), err),
+            }
+        }
+        themes
+    })
+}
+
+/// Finds a theme by its id, falling back on the default one.
+pub fn find(id: &str) -> &'static ThemeManifest {
+    let themes = available();
+    themes.iter().find(|theme| theme.id == id).unwrap_or(&themes[0])
+}
+
+/// The theme cells are currently drawn with. Replaced whenever the user picks another theme or palette.
+#[derive(Resource)]
+pub struct Theme {
+    manifest: &'static ThemeManifest,
+    palette: Palette,
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+impl Theme {
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// Size of a tile in pixels, before any scaling.
+    pub fn tile_size(&self) -> u32 {
+        self.manifest.tile_size
+    }
+
+    /// Where the tile of the given state is in the atlas (in UV coordinates), and its tint.
+    pub fn tile(&self, state: CellTile) -> (Rect, Color) {
+        let Tile { index, tint } = self.manifest.tile(state, self.palette);
+        let (columns, rows) = (self.manifest.columns as f32, self.manifest.rows as f32);
+        let (column, row) = ((index % self.manifest.columns as usize) as f32, (index / self.manifest.columns as usize) as f32);
+
+        let uv = Rect::new(column / columns, row / rows, (column + 1.0) / columns, (row + 1.0) / rows);
+        (uv, tint)
+    }
+
+    /// A sprite showing the given state.
+    pub fn sprite(&self, state: CellTile) -> Sprite {
+        let tile = self.manifest.tile(state, self.palette);
+        Sprite {
+            image: self.image.clone(),
+            texture_atlas: Some(TextureAtlas { layout: self.layout.clone(), index: tile.index }),
+            color: tile.tint,
+            ..default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl Theme {
+    /// The default theme, without loading its atlas.
+    pub(crate) fn unloaded() -> Self {
+        Self { manifest: find(DEFAULT_THEME), palette: Palette::default(), image: Handle::default(), layout: Handle::default() }
+    }
+}
+
+/// Loads the theme and palette picked in the user config, unless they are already in use.
+fn load_theme(
+    config: Res<UserConfig>,
+    theme: Option<Res<Theme>>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+) {
+    let manifest = find(&config.theme);
+    if theme.is_some_and(|theme| theme.manifest.id == manifest.id && theme.palette == config.palette) { return };
+
+    let layout = TextureAtlasLayout::from_grid(UVec2::splat(manifest.tile_size), manifest.columns, manifest.rows, None, None);
+    commands.insert_resource(Theme {
+        manifest,
+        palette: config.palette,
+        image: asset_server.load(&manifest.atlas),
+        layout: layouts.add(layout),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default manifest, without the lines setting the given tile.
+    fn manifest_without(tile: &str) -> Result<ThemeManifest, String> {
+        let content: Vec<&str> = DEFAULT_MANIFEST.lines().filter(|line| !line.starts_with(&format!("{tile} ="))).collect();
+        ThemeManifest::parse("test", &content.join("\n"))
+    }
+
+    #[test]
+    fn pressed_falls_back_to_hidden() {
+        let manifest = manifest_without("pressed").unwrap();
+        for palette in Palette::ALL {
+            assert_eq!(manifest.tile(CellTile::Pressed, palette).index, manifest.tile(CellTile::Hidden, palette).index);
+        }
+    }
+
+    #[test]
+    fn question_is_required() {
+        let err = manifest_without("question").unwrap_err();
+        assert!(err.contains("question"), "{err}");
+    }
+}
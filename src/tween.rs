@@ -0,0 +1,65 @@
+/// Seconds a tile takes to pop in.
+const POP_DURATION: f32 = 0.18;
+/// Seconds a tile takes to shrink away.
+const SHRINK_DURATION: f32 = 0.12;
+/// How far a popping tile grows past its size before settling, as in the "back" easing curves.
+const POP_OVERSHOOT: f32 = 1.7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TweenKind {
+    /// Grows from nothing to its size, overshooting slightly.
+    PopIn,
+    /// Shrinks to nothing, then disappears.
+    ShrinkOut,
+}
+impl TweenKind {
+    fn duration(&self) -> f32 {
+        match self {
+            TweenKind::PopIn => POP_DURATION,
+            TweenKind::ShrinkOut => SHRINK_DURATION,
+        }
+    }
+
+    /// Scale of the tile, given how far along the tween is (0 to 1).
+    fn scale(&self, t: f32) -> f32 {
+        match self {
+            TweenKind::PopIn => {
+                let t = t - 1.0;
+                1.0 + (POP_OVERSHOOT + 1.0) * t * t * t + POP_OVERSHOOT * t * t
+            },
+            TweenKind::ShrinkOut => 1.0 - t * t,
+        }
+    }
+}
+
+/// Animates the scale of a tile. Purely visual: the state of whatever the tile shows has already changed,
+/// so nothing waits on a tween to finish.
+#[derive(Clone, Copy, Debug)]
+pub struct Tween {
+    kind: TweenKind,
+    /// Seconds to wait before starting, the tile keeps the look of the start of the tween meanwhile.
+    delay: f32,
+    elapsed: f32,
+}
+impl Tween {
+    pub fn new(kind: TweenKind, delay: f32) -> Self {
+        Self { kind, delay, elapsed: 0.0 }
+    }
+
+    pub fn kind(&self) -> TweenKind { self.kind }
+
+    /// Moves the tween along by the given seconds. Returns whether it has finished.
+    pub fn advance(&mut self, seconds: f32) -> bool {
+        self.elapsed += seconds;
+        self.progress() >= 1.0
+    }
+
+    /// Current scale of the tile.
+    pub fn scale(&self) -> f32 {
+        self.kind.scale(self.progress())
+    }
+
+    fn progress(&self) -> f32 {
+        ((self.elapsed - self.delay) / self.kind.duration()).clamp(0.0, 1.0)
+    }
+}